@@ -1,12 +1,49 @@
-use crate::entities::WatchedGame;
+use crate::data_providers::{provider, GameProvider, HttpConfig, ProviderKind};
+use crate::entities::{Region, WatchedGame};
+use crate::scheduler::Schedule;
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
+use chrono::Duration;
 use log::debug;
 use serde::Deserialize;
 use std::path::PathBuf;
 
+mod rule;
+mod template;
+
+pub(crate) use rule::{Rule, RuleInput};
+pub(crate) use template::Template;
+
+const DEFAULT_MAX_ITEMS_IN_NOTIFICATION: usize = 10;
+
+/// Fields available in the title template of the combined notification.
+pub(crate) const TITLE_FIELDS: &[&str] = &["count"];
+
+/// Fields available in the notification item template.
+pub(crate) const ITEM_FIELDS: &[&str] = &[
+    "title",
+    "region",
+    "price",
+    "regular_price",
+    "discount",
+    "currency",
+    "sale_end",
+    "previous_price",
+    "all_time_low",
+];
+
 #[derive(Debug, Deserialize)]
 pub struct Config {
+    #[serde(default)]
+    debug: bool,
+    #[serde(default)]
+    provider: ProviderKind,
+    region: Option<Region>,
+    schedule: Option<Schedule>,
+    #[serde(default)]
+    notification: NotificationConfig,
+    #[serde(default)]
+    http: HttpConfig,
     #[serde(rename = "watched_game")]
     watched_games: Vec<WatchedGame>,
 }
@@ -14,12 +51,221 @@ pub struct Config {
 impl Config {
     pub fn load(content: &str) -> Result<Self> {
         let cfg: Config = toml::from_str(content)?;
-        debug!("loaded config: {:#?}", cfg);
+        debug!("loaded config: {cfg:#?}");
+        let provider = provider(cfg.provider, &cfg.http);
+        cfg.validate_regions(provider.as_ref())?;
+        cfg.validate_store_ids(provider.as_ref())?;
         Ok(cfg)
     }
 
+    fn validate_regions(&self, provider: &dyn GameProvider) -> Result<()> {
+        for watched_game in self.watched_games() {
+            let region = provider.region_for(&watched_game);
+            if !provider.regions().contains(&region) {
+                bail!(
+                    "region '{region}' of '{}' is not supported by {}",
+                    watched_game.title(),
+                    provider.name()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Providers searching by the title can't find games by the store ID alone, nor match product
+    /// codes, such games would silently never be notified.
+    fn validate_store_ids(&self, provider: &dyn GameProvider) -> Result<()> {
+        if provider.capabilities().store_id_search {
+            return Ok(());
+        }
+        for watched_game in self.watched_games() {
+            if watched_game.product_code().is_some() {
+                bail!(
+                    "product_code of '{}' is not supported by {}, use 'nsuid' instead",
+                    watched_game.title(),
+                    provider.name()
+                );
+            }
+            if watched_game.is_searched_by_store_id() {
+                bail!(
+                    "'{}' needs a 'title' or 'pattern' to be searched for in {}",
+                    watched_game.title(),
+                    provider.name()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Watched games with the global region applied to those which don't set their own.
     pub fn watched_games(&self) -> Vec<WatchedGame> {
-        self.watched_games.clone()
+        self.watched_games
+            .iter()
+            .cloned()
+            .map(|watched_game| watched_game.or_region(self.region))
+            .collect()
+    }
+
+    pub(crate) fn debug(&self) -> bool {
+        self.debug
+    }
+
+    pub(crate) fn provider(&self) -> ProviderKind {
+        self.provider
+    }
+
+    pub(crate) fn http(&self) -> &HttpConfig {
+        &self.http
+    }
+
+    pub(crate) fn schedule(&self) -> Option<&Schedule> {
+        self.schedule.as_ref()
+    }
+
+    pub(crate) fn notification(&self) -> &NotificationConfig {
+        &self.notification
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(try_from = "RawNotificationConfig")]
+pub(crate) struct NotificationConfig {
+    renotify_after_hours: Option<u32>,
+    on_empty: OnEmpty,
+    mode: NotificationMode,
+    title: Option<Template>,
+    item: Option<Template>,
+    max_items: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct RawNotificationConfig {
+    renotify_after_hours: Option<u32>,
+    #[serde(default)]
+    on_empty: OnEmpty,
+    #[serde(default)]
+    mode: NotificationMode,
+    title: Option<String>,
+    item: Option<String>,
+    max_items: Option<usize>,
+}
+
+impl TryFrom<RawNotificationConfig> for NotificationConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: RawNotificationConfig) -> Result<Self> {
+        let title = raw
+            .title
+            .map(|title| Template::parse(&title, raw.mode.title_fields()))
+            .transpose()
+            .map_err(|e| anyhow!("invalid notification title template: {e}"))?;
+        let item = raw
+            .item
+            .map(|item| Template::parse(&item, ITEM_FIELDS))
+            .transpose()
+            .map_err(|e| anyhow!("invalid notification item template: {e}"))?;
+        if raw.max_items == Some(0) {
+            bail!("max_items of notification has to be greater than 0");
+        }
+        Ok(Self {
+            renotify_after_hours: raw.renotify_after_hours,
+            on_empty: raw.on_empty,
+            mode: raw.mode,
+            title,
+            item,
+            max_items: raw.max_items,
+        })
+    }
+}
+
+/// What to do when no games are on sale.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum OnEmpty {
+    /// Do nothing.
+    Silent,
+    /// Send desktop notification.
+    #[default]
+    Notify,
+    /// Only write heartbeat message to the logs.
+    Log,
+}
+
+/// How found deals are split into notifications.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum NotificationMode {
+    /// One notification listing all deals.
+    #[default]
+    Combined,
+    /// Notification for each deal, with the box art as the icon.
+    PerGame,
+}
+
+impl NotificationMode {
+    /// Combined notification is titled with the number of games, the one per game with fields of
+    /// the game, like its item.
+    fn title_fields(self) -> &'static [&'static str] {
+        match self {
+            NotificationMode::Combined => TITLE_FIELDS,
+            NotificationMode::PerGame => ITEM_FIELDS,
+        }
+    }
+}
+
+impl NotificationConfig {
+    /// How long to wait before notifying again about unchanged deal. `None` means never.
+    pub(crate) fn renotify_after(&self) -> Option<Duration> {
+        self.renotify_after_hours
+            .map(|hours| Duration::hours(i64::from(hours)))
+    }
+
+    pub(crate) fn on_empty(&self) -> OnEmpty {
+        self.on_empty
+    }
+
+    pub(crate) fn mode(&self) -> NotificationMode {
+        self.mode
+    }
+
+    /// Template of the notification title. `None` keeps the default title.
+    pub(crate) fn title(&self) -> Option<&Template> {
+        self.title.as_ref()
+    }
+
+    /// Template of a single game line. `None` keeps the default format.
+    pub(crate) fn item(&self) -> Option<&Template> {
+        self.item.as_ref()
+    }
+
+    /// Number of games listed in the notification, others are summarized as "and N more".
+    pub(crate) fn max_items(&self) -> usize {
+        self.max_items.unwrap_or(DEFAULT_MAX_ITEMS_IN_NOTIFICATION)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_mode(mut self, mode: NotificationMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_title(mut self, title: &str) -> Self {
+        let template = Template::parse(title, self.mode.title_fields());
+        self.title = Some(template.expect("invalid template"));
+        self
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_item(mut self, item: &str) -> Self {
+        self.item = Some(Template::parse(item, ITEM_FIELDS).expect("invalid template"));
+        self
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
     }
 }
 
@@ -36,6 +282,7 @@ pub fn sweetch_dir() -> PathBuf {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::entities::{Criteria, TitleMatch};
     use crate::testutils;
 
     #[test]
@@ -121,6 +368,711 @@ mod test {
         let _not_important = Config::load(config_content).unwrap();
     }
 
+    #[test]
+    fn test_load_config_with_debug_enabled() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            debug = true
+
+            [[watched_game]]
+            title = "Game 1 title here"
+         "#;
+
+        // when
+        let cfg = Config::load(config_content).unwrap();
+
+        // then
+        assert!(cfg.debug());
+    }
+
+    #[test]
+    fn test_debug_is_disabled_by_default() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [[watched_game]]
+            title = "Game 1 title here"
+         "#;
+
+        // when
+        let cfg = Config::load(config_content).unwrap();
+
+        // then
+        assert!(!cfg.debug());
+    }
+
+    #[test]
+    fn test_load_config_with_provider() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            provider = "nintendo_america"
+
+            [[watched_game]]
+            title = "Game 1 title here"
+         "#;
+
+        // when
+        let cfg = Config::load(config_content).unwrap();
+
+        // then
+        assert_eq!(cfg.provider(), ProviderKind::NintendoAmerica);
+    }
+
+    #[test]
+    fn test_nintendo_europe_is_default_provider() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [[watched_game]]
+            title = "Game 1 title here"
+         "#;
+
+        // when
+        let cfg = Config::load(config_content).unwrap();
+
+        // then
+        assert_eq!(cfg.provider(), ProviderKind::NintendoEurope);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_load_config_with_unknown_provider() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            provider = "nintendo_mars"
+
+            [[watched_game]]
+            title = "Game 1 title here"
+         "#;
+
+        // should_panic
+        let _not_important = Config::load(config_content).unwrap();
+    }
+
+    #[test]
+    fn test_load_config_with_global_and_per_game_region() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            region = "de"
+
+            [[watched_game]]
+            title = "Game 1 title here"
+
+            [[watched_game]]
+            title = "Game 2 title here"
+            region = "pl"
+         "#;
+
+        // when
+        let cfg = Config::load(config_content).unwrap();
+
+        // then
+        assert_eq!(
+            cfg.watched_games(),
+            vec![
+                WatchedGame::new("Game 1 title here").with_region(Region::De),
+                WatchedGame::new("Game 2 title here").with_region(Region::Pl),
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_load_config_with_region_not_supported_by_provider() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            provider = "nintendo_america"
+
+            [[watched_game]]
+            title = "Game 1 title here"
+            region = "de"
+         "#;
+
+        // should_panic
+        let _not_important = Config::load(config_content).unwrap();
+    }
+
+    #[test]
+    fn test_load_config_with_renotify_interval() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [notification]
+            renotify_after_hours = 48
+
+            [[watched_game]]
+            title = "Game 1 title here"
+         "#;
+
+        // when
+        let cfg = Config::load(config_content).unwrap();
+
+        // then
+        assert_eq!(
+            cfg.notification().renotify_after(),
+            Some(Duration::hours(48))
+        );
+    }
+
+    #[test]
+    fn test_deals_are_not_renotified_by_default() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [[watched_game]]
+            title = "Game 1 title here"
+         "#;
+
+        // when
+        let cfg = Config::load(config_content).unwrap();
+
+        // then
+        assert_eq!(cfg.notification().renotify_after(), None);
+    }
+
+    #[test]
+    fn test_load_config_with_on_empty() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [notification]
+            on_empty = "log"
+
+            [[watched_game]]
+            title = "Game 1 title here"
+         "#;
+
+        // when
+        let cfg = Config::load(config_content).unwrap();
+
+        // then
+        assert_eq!(cfg.notification().on_empty(), OnEmpty::Log);
+    }
+
+    #[test]
+    fn test_load_config_with_notification_templates() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [notification]
+            title = "{{count}} games on sale"
+            item = "{{title}}: {{price}} {{currency}}"
+            max_items = 5
+
+            [[watched_game]]
+            title = "Game 1 title here"
+         "#;
+
+        // when
+        let cfg = Config::load(config_content).unwrap();
+
+        // then
+        let notification = cfg.notification();
+        let expected = NotificationConfig::default()
+            .with_title("{{count}} games on sale")
+            .with_item("{{title}}: {{price}} {{currency}}");
+        assert_eq!(notification.title(), expected.title());
+        assert_eq!(notification.item(), expected.item());
+        assert_eq!(notification.max_items(), 5);
+        assert_eq!(notification.mode(), NotificationMode::Combined);
+    }
+
+    #[test]
+    fn test_load_config_with_per_game_title_template() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [notification]
+            mode = "per_game"
+            title = "{{title}} for {{price}} {{currency}}"
+
+            [[watched_game]]
+            title = "Game 1 title here"
+         "#;
+
+        // when
+        let cfg = Config::load(config_content).unwrap();
+
+        // then
+        let expected = NotificationConfig::default()
+            .with_mode(NotificationMode::PerGame)
+            .with_title("{{title}} for {{price}} {{currency}}");
+        assert_eq!(cfg.notification().title(), expected.title());
+        assert_eq!(cfg.notification().mode(), NotificationMode::PerGame);
+    }
+
+    #[test]
+    fn test_load_config_with_count_in_per_game_title_template() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [notification]
+            mode = "per_game"
+            title = "{{count}} games on sale"
+
+            [[watched_game]]
+            title = "Game 1 title here"
+         "#;
+
+        // when
+        let cfg = Config::load(config_content);
+
+        // then
+        assert!(cfg
+            .unwrap_err()
+            .to_string()
+            .starts_with("invalid notification title template: unknown field 'count' at column 1"));
+    }
+
+    #[test]
+    fn test_load_config_with_invalid_notification_template() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [notification]
+            item = "{{title}}: {{prize}}"
+
+            [[watched_game]]
+            title = "Game 1 title here"
+         "#;
+
+        // when
+        let cfg = Config::load(config_content);
+
+        // then
+        assert!(cfg
+            .unwrap_err()
+            .to_string()
+            .starts_with("invalid notification item template: unknown field 'prize' at column 12"));
+    }
+
+    #[test]
+    fn test_on_empty_defaults_to_notify() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [[watched_game]]
+            title = "Game 1 title here"
+         "#;
+
+        // when
+        let cfg = Config::load(config_content).unwrap();
+
+        // then
+        assert_eq!(cfg.notification().on_empty(), OnEmpty::Notify);
+    }
+
+    #[test]
+    fn test_load_config_with_title_match() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [[watched_game]]
+            title = "Game 1 title here"
+
+            [[watched_game]]
+            title = "Game 2 title here"
+            match = "fuzzy"
+            min_similarity = 0.8
+         "#;
+
+        // when
+        let cfg = Config::load(config_content).unwrap();
+
+        // then
+        assert_eq!(
+            cfg.watched_games(),
+            vec![
+                WatchedGame::new("Game 1 title here"),
+                WatchedGame::new("Game 2 title here")
+                    .with_title_match(TitleMatch::Fuzzy)
+                    .with_min_similarity(0.8),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_load_config_with_invalid_min_similarity() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [[watched_game]]
+            title = "Game 1 title here"
+            match = "fuzzy"
+            min_similarity = 85
+         "#;
+
+        // when
+        let cfg = Config::load(config_content);
+
+        // then
+        assert!(cfg.is_err());
+    }
+
+    #[test]
+    fn test_load_config_with_pattern() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [[watched_game]]
+            pattern = "LEGO*"
+
+            [[watched_game]]
+            title = "Zelda"
+            pattern = "^The Legend of Zelda"
+            pattern_syntax = "regex"
+         "#;
+
+        // when
+        let cfg = Config::load(config_content).unwrap();
+
+        // then
+        let watched_games = cfg.watched_games();
+        assert_eq!(watched_games[0].title(), "LEGO");
+        assert!(watched_games[0].pattern().is_some());
+        assert_eq!(watched_games[1].title(), "Zelda");
+        assert!(watched_games[1].pattern().is_some());
+    }
+
+    #[test]
+    fn test_load_config_with_store_ids() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [[watched_game]]
+            nsuid = "70010000025453"
+
+            [[watched_game]]
+            title = "Minecraft Dungeons"
+            product_code = "HACPAURNA"
+         "#;
+
+        // when
+        let cfg = Config::load(config_content).unwrap();
+
+        // then
+        assert_eq!(
+            cfg.watched_games(),
+            vec![
+                WatchedGame::new("70010000025453").with_nsuid("70010000025453"),
+                WatchedGame::new("Minecraft Dungeons").with_product_code("HACPAURNA"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_load_config_with_product_code_not_supported_by_provider() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            provider = "nintendo_america"
+
+            [[watched_game]]
+            title = "Minecraft Dungeons"
+            product_code = "HACPAURNA"
+         "#;
+
+        // when
+        let cfg = Config::load(config_content);
+
+        // then
+        assert_eq!(
+            cfg.unwrap_err().to_string(),
+            "product_code of 'Minecraft Dungeons' is not supported by Nintendo of America, \
+            use 'nsuid' instead"
+        );
+    }
+
+    #[test]
+    fn test_load_config_with_only_nsuid_not_supported_by_provider() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            provider = "nintendo_america"
+
+            [[watched_game]]
+            nsuid = "70010000025453"
+         "#;
+
+        // when
+        let cfg = Config::load(config_content);
+
+        // then
+        assert_eq!(
+            cfg.unwrap_err().to_string(),
+            "'70010000025453' needs a 'title' or 'pattern' to be searched for in Nintendo of America"
+        );
+    }
+
+    #[test]
+    fn test_load_config_with_nsuid_and_title_for_provider_searching_by_title() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            provider = "nintendo_america"
+
+            [[watched_game]]
+            title = "Minecraft Dungeons"
+            nsuid = "70010000025453"
+         "#;
+
+        // when
+        let cfg = Config::load(config_content).unwrap();
+
+        // then
+        assert_eq!(
+            cfg.watched_games(),
+            vec![WatchedGame::new("Minecraft Dungeons").with_nsuid("70010000025453")]
+        );
+    }
+
+    #[test]
+    fn test_load_config_with_discount_criteria() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [[watched_game]]
+            title = "Game 1 title here"
+            acceptable_price = 10.0
+            min_discount_percent = 50
+            criteria = "any"
+         "#;
+
+        // when
+        let cfg = Config::load(config_content).unwrap();
+
+        // then
+        assert_eq!(
+            cfg.watched_games(),
+            vec![WatchedGame::new("Game 1 title here")
+                .with_acceptable_price(10.0)
+                .with_min_discount_percent(50.0)
+                .with_criteria(Criteria::Any)]
+        );
+    }
+
+    #[test]
+    fn test_load_config_with_price_history_criteria() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [[watched_game]]
+            title = "Game 1 title here"
+            only_all_time_low = true
+
+            [[watched_game]]
+            title = "Game 2 title here"
+            within_percent_of_low = 10
+            notify_on_price_drop = true
+         "#;
+
+        // when
+        let cfg = Config::load(config_content).unwrap();
+
+        // then
+        assert_eq!(
+            cfg.watched_games(),
+            vec![
+                WatchedGame::new("Game 1 title here").with_max_percent_above_low(0.0),
+                WatchedGame::new("Game 2 title here")
+                    .with_max_percent_above_low(10.0)
+                    .with_notify_on_price_drop(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_load_config_with_invalid_min_discount_percent() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [[watched_game]]
+            title = "Game 1 title here"
+            min_discount_percent = 150
+         "#;
+
+        // when
+        let cfg = Config::load(config_content);
+
+        // then
+        assert!(cfg.is_err());
+    }
+
+    #[test]
+    fn test_load_config_with_rule() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [[watched_game]]
+            title = "Game 1 title here"
+            rule = "discount >= 40% and price <= 15"
+         "#;
+
+        // when
+        let cfg = Config::load(config_content).unwrap();
+
+        // then
+        assert_eq!(
+            cfg.watched_games(),
+            vec![WatchedGame::new("Game 1 title here").with_rule("discount >= 40% and price <= 15")]
+        );
+    }
+
+    #[test]
+    fn test_load_config_with_invalid_rule() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [[watched_game]]
+            title = "Game 1 title here"
+
+            [[watched_game]]
+            title = "Game 2 title here"
+            rule = "discount >= 40% annd price <= 15"
+         "#;
+
+        // when
+        let error = Config::load(config_content).unwrap_err();
+
+        // then
+        assert_eq!(
+            error.to_string(),
+            "invalid rule 'discount >= 40% annd price <= 15': unexpected 'annd' at column 17 \
+            for key `watched_game.rule` at line 5 column 13"
+        );
+    }
+
+    #[test]
+    fn test_load_config_with_invalid_regex() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [[watched_game]]
+            pattern = "Zelda ("
+            pattern_syntax = "regex"
+         "#;
+
+        // when
+        let cfg = Config::load(config_content);
+
+        // then
+        assert!(cfg.is_err());
+    }
+
+    #[test]
+    fn test_load_config_without_title_and_pattern() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [[watched_game]]
+            acceptable_price = 10.0
+         "#;
+
+        // when
+        let cfg = Config::load(config_content);
+
+        // then
+        assert!(cfg.is_err());
+    }
+
+    #[test]
+    fn test_load_config_with_unsearchable_title() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [[watched_game]]
+            title = "?!"
+         "#;
+
+        // when
+        let cfg = Config::load(config_content);
+
+        // then
+        assert!(cfg
+            .unwrap_err()
+            .to_string()
+            .starts_with("title '?!' has to contain a letter or a digit to be searched for"));
+    }
+
+    #[test]
+    fn test_load_config_with_partial_http_config() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [http]
+            max_retries = 3
+
+            [[watched_game]]
+            title = "Game 1 title here"
+         "#;
+
+        // when
+        let cfg = Config::load(config_content).unwrap();
+
+        // then
+        assert_eq!(cfg.http(), &HttpConfig::default());
+    }
+
+    #[test]
+    fn test_load_config_with_schedule() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [schedule]
+            run_at = ["7:00 pm", "8:00 am"]
+
+            [[watched_game]]
+            title = "Game 1 title here"
+         "#;
+
+        // when
+        let cfg = Config::load(config_content).unwrap();
+
+        // then
+        assert_eq!(
+            cfg.schedule(),
+            Some(&Schedule::new(&["8:00 am", "7:00 pm"]).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_load_config_without_schedule() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [[watched_game]]
+            title = "Game 1 title here"
+         "#;
+
+        // when
+        let cfg = Config::load(config_content).unwrap();
+
+        // then
+        assert_eq!(cfg.schedule(), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_load_config_with_invalid_schedule_time() {
+        testutils::setup_logger();
+        // given
+        let config_content = r#"
+            [schedule]
+            run_at = ["7:00 xm"]
+
+            [[watched_game]]
+            title = "Game 1 title here"
+         "#;
+
+        // should_panic
+        let _not_important = Config::load(config_content).unwrap();
+    }
+
     #[test]
     fn test_config_path() {
         // given
@@ -0,0 +1,502 @@
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+
+// prices are compared with a tolerance, as they come as floats from the stores
+const PRICE_EPSILON: f64 = 0.001;
+
+/// Expression deciding if a game is worth notifying about, e.g. `discount >= 40% and price <= 15`.
+///
+/// Rules compare fields `price`, `regular_price` and `discount` with numbers using `<`, `<=`, `>`,
+/// `>=`, `==` and `!=`, refer to flags `on_sale` and `is_all_time_low` and combine all of them
+/// with `and`, `or`, `not` and parentheses.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub(crate) struct Rule {
+    source: String,
+    expr: Expr,
+}
+
+impl TryFrom<String> for Rule {
+    type Error = anyhow::Error;
+
+    fn try_from(source: String) -> Result<Self> {
+        let expr = Parser::new(&source)
+            .and_then(Parser::parse)
+            .map_err(|e| anyhow!("invalid rule '{source}': {e}"))?;
+        Ok(Self { source, expr })
+    }
+}
+
+impl PartialEq for Rule {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Rule {
+    #[cfg(test)]
+    pub(crate) fn new(source: &str) -> Result<Self> {
+        Self::try_from(source.to_string())
+    }
+
+    pub(crate) fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub(crate) fn evaluate(&self, input: &RuleInput) -> bool {
+        self.expr.evaluate(input)
+    }
+}
+
+/// Values of a game which rules can refer to.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct RuleInput {
+    pub(crate) price: f64,
+    pub(crate) regular_price: Option<f64>,
+    pub(crate) discount: f64,
+    pub(crate) on_sale: bool,
+    pub(crate) all_time_low: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Field, Op, f64),
+    Flag(Flag),
+}
+
+impl Expr {
+    fn evaluate(&self, input: &RuleInput) -> bool {
+        match self {
+            Expr::And(left, right) => left.evaluate(input) && right.evaluate(input),
+            Expr::Or(left, right) => left.evaluate(input) || right.evaluate(input),
+            Expr::Not(expr) => !expr.evaluate(input),
+            Expr::Compare(field, op, value) => field
+                .value(input)
+                .is_some_and(|field_value| op.compare(field_value, *value)),
+            Expr::Flag(flag) => flag.value(input),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Price,
+    RegularPrice,
+    Discount,
+}
+
+impl Field {
+    fn value(self, input: &RuleInput) -> Option<f64> {
+        match self {
+            Field::Price => Some(input.price),
+            Field::RegularPrice => input.regular_price,
+            Field::Discount => Some(input.discount),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Flag {
+    OnSale,
+    AllTimeLow,
+}
+
+impl Flag {
+    fn value(self, input: &RuleInput) -> bool {
+        match self {
+            Flag::OnSale => input.on_sale,
+            Flag::AllTimeLow => input.all_time_low,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Op {
+    fn compare(self, left: f64, right: f64) -> bool {
+        let equal = (left - right).abs() < PRICE_EPSILON;
+        match self {
+            Op::Lt => left < right && !equal,
+            Op::Le => left < right || equal,
+            Op::Gt => left > right && !equal,
+            Op::Ge => left > right || equal,
+            Op::Eq => equal,
+            Op::Ne => !equal,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Percent,
+    Word(String),
+    Op(Op),
+    LParen,
+    RParen,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Number(number) => format!("number {number}"),
+            Token::Percent => "'%'".into(),
+            Token::Word(word) => format!("'{word}'"),
+            Token::Op(op) => format!("operator {op:?}"),
+            Token::LParen => "'('".into(),
+            Token::RParen => "')'".into(),
+        }
+    }
+}
+
+/// Token together with its column in the rule, used in error messages.
+type Spanned = (usize, Token);
+
+fn tokenize(source: &str) -> Result<Vec<Spanned>> {
+    let chars = source.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+    while idx < chars.len() {
+        let c = chars[idx];
+        let column = idx + 1;
+        let next = chars.get(idx + 1).copied();
+        let (token, len) = match c {
+            c if c.is_whitespace() => {
+                idx += 1;
+                continue;
+            }
+            '(' => (Token::LParen, 1),
+            ')' => (Token::RParen, 1),
+            '%' => (Token::Percent, 1),
+            '<' if next == Some('=') => (Token::Op(Op::Le), 2),
+            '<' => (Token::Op(Op::Lt), 1),
+            '>' if next == Some('=') => (Token::Op(Op::Ge), 2),
+            '>' => (Token::Op(Op::Gt), 1),
+            '=' if next == Some('=') => (Token::Op(Op::Eq), 2),
+            '!' if next == Some('=') => (Token::Op(Op::Ne), 2),
+            c if c.is_ascii_digit() || c == '.' => {
+                let len = chars[idx..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_digit() || **c == '.')
+                    .count();
+                let number = chars[idx..idx + len].iter().collect::<String>();
+                let number = number
+                    .parse()
+                    .map_err(|_| anyhow!("invalid number '{number}' at column {column}"))?;
+                (Token::Number(number), len)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let len = chars[idx..]
+                    .iter()
+                    .take_while(|c| c.is_alphanumeric() || **c == '_')
+                    .count();
+                let word = chars[idx..idx + len].iter().collect::<String>();
+                (Token::Word(word.to_lowercase()), len)
+            }
+            c => bail!("unexpected character '{c}' at column {column}"),
+        };
+        tokens.push((column, token));
+        idx += len;
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser of the grammar:
+///
+/// ```text
+/// or         = and ("or" and)*
+/// and        = not ("and" not)*
+/// not        = "not" not | primary
+/// primary    = "(" or ")" | comparison | flag
+/// comparison = field op number ["%"]
+/// ```
+struct Parser {
+    tokens: Vec<Spanned>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(source: &str) -> Result<Self> {
+        Ok(Self {
+            tokens: tokenize(source)?,
+            pos: 0,
+        })
+    }
+
+    fn parse(mut self) -> Result<Expr> {
+        if self.tokens.is_empty() {
+            bail!("rule is empty");
+        }
+        let expr = self.parse_or()?;
+        if let Some((column, token)) = self.tokens.get(self.pos) {
+            bail!("unexpected {} at column {column}", token.describe());
+        }
+        Ok(expr)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut expr = self.parse_and()?;
+        while self.next_is_word("or") {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut expr = self.parse_not()?;
+        while self.next_is_word("and") {
+            self.pos += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_not()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr> {
+        if self.next_is_word("not") {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr> {
+        let (column, token) = self.advance("a field, a flag or '('")?;
+        match token {
+            Token::LParen => {
+                let expr = self.parse_or()?;
+                match self.advance("')'")? {
+                    (_, Token::RParen) => Ok(expr),
+                    (column, token) => {
+                        bail!(
+                            "expected ')', found {} at column {column}",
+                            token.describe()
+                        )
+                    }
+                }
+            }
+            Token::Word(word) => match word.as_str() {
+                "on_sale" => Ok(Expr::Flag(Flag::OnSale)),
+                "is_all_time_low" => Ok(Expr::Flag(Flag::AllTimeLow)),
+                "price" => self.parse_comparison(Field::Price),
+                "regular_price" => self.parse_comparison(Field::RegularPrice),
+                "discount" => self.parse_comparison(Field::Discount),
+                _ => bail!(
+                    "unknown name '{word}' at column {column}, expected one of: price, \
+                    regular_price, discount, on_sale, is_all_time_low"
+                ),
+            },
+            token => bail!(
+                "expected a field, a flag or '(', found {} at column {column}",
+                token.describe()
+            ),
+        }
+    }
+
+    fn parse_comparison(&mut self, field: Field) -> Result<Expr> {
+        let op = match self.advance("a comparison operator")? {
+            (_, Token::Op(op)) => op,
+            (column, token) => bail!(
+                "expected a comparison operator, found {} at column {column}",
+                token.describe()
+            ),
+        };
+        let value = match self.advance("a number")? {
+            (_, Token::Number(value)) => value,
+            (column, token) => {
+                bail!(
+                    "expected a number, found {} at column {column}",
+                    token.describe()
+                )
+            }
+        };
+        if let Some((column, Token::Percent)) = self.tokens.get(self.pos) {
+            if field != Field::Discount {
+                bail!("'%' at column {column} can be used only with discount");
+            }
+            self.pos += 1;
+        }
+        Ok(Expr::Compare(field, op, value))
+    }
+
+    fn next_is_word(&self, expected: &str) -> bool {
+        matches!(self.tokens.get(self.pos), Some((_, Token::Word(word))) if word == expected)
+    }
+
+    fn advance(&mut self, expected: &str) -> Result<Spanned> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| anyhow!("expected {expected}, but the rule ends"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testutils;
+
+    fn input(price: f64, regular_price: f64) -> RuleInput {
+        RuleInput {
+            price,
+            regular_price: Some(regular_price),
+            discount: (regular_price - price) / regular_price * 100.0,
+            on_sale: price < regular_price,
+            all_time_low: false,
+        }
+    }
+
+    #[test]
+    fn test_rule_with_and() {
+        testutils::setup_logger();
+        // given
+        let rule = Rule::new("discount >= 40% and price <= 15").unwrap();
+
+        // when
+        let results = [
+            rule.evaluate(&input(12.0, 20.0)),
+            rule.evaluate(&input(15.0, 20.0)),
+            rule.evaluate(&input(18.0, 40.0)),
+        ];
+
+        // then
+        assert_eq!(results, [true, false, false]);
+    }
+
+    #[test]
+    fn test_rule_with_or_and_flag() {
+        testutils::setup_logger();
+        // given
+        let rule = Rule::new("is_all_time_low or price < 5").unwrap();
+        let all_time_low = RuleInput {
+            all_time_low: true,
+            ..input(30.0, 30.0)
+        };
+
+        // when
+        let results = [
+            rule.evaluate(&all_time_low),
+            rule.evaluate(&input(4.99, 10.0)),
+            rule.evaluate(&input(5.0, 10.0)),
+        ];
+
+        // then
+        assert_eq!(results, [true, true, false]);
+    }
+
+    #[test]
+    fn test_rule_precedence_and_parentheses() {
+        testutils::setup_logger();
+        // given
+        let without_parentheses = Rule::new("on_sale or price < 5 and discount > 50").unwrap();
+        let with_parentheses = Rule::new("(on_sale or price < 5) and discount > 50").unwrap();
+
+        // when
+        let results = [
+            without_parentheses.evaluate(&input(15.0, 20.0)),
+            with_parentheses.evaluate(&input(15.0, 20.0)),
+        ];
+
+        // then
+        assert_eq!(results, [true, false]);
+    }
+
+    #[test]
+    fn test_rule_with_not() {
+        testutils::setup_logger();
+        // given
+        let rule = Rule::new("not on_sale and regular_price <= 10").unwrap();
+
+        // when
+        let results = [
+            rule.evaluate(&input(10.0, 10.0)),
+            rule.evaluate(&input(5.0, 10.0)),
+        ];
+
+        // then
+        assert_eq!(results, [true, false]);
+    }
+
+    #[test]
+    fn test_rule_with_missing_regular_price() {
+        testutils::setup_logger();
+        // given
+        let rule = Rule::new("regular_price != 10").unwrap();
+        let input = RuleInput {
+            regular_price: None,
+            ..input(5.0, 10.0)
+        };
+
+        // when
+        let result = rule.evaluate(&input);
+
+        // then
+        assert!(!result);
+    }
+
+    #[test]
+    fn test_rule_with_unknown_name() {
+        testutils::setup_logger();
+        // given
+        let source = "discount >= 40% annd price <= 15";
+
+        // when
+        let error = Rule::new(source).unwrap_err();
+
+        // then
+        assert_eq!(
+            error.to_string(),
+            "invalid rule 'discount >= 40% annd price <= 15': unexpected 'annd' at column 17"
+        );
+    }
+
+    #[test]
+    fn test_rule_with_percent_after_price() {
+        testutils::setup_logger();
+        // given
+        let source = "price <= 15%";
+
+        // when
+        let error = Rule::new(source).unwrap_err();
+
+        // then
+        assert_eq!(
+            error.to_string(),
+            "invalid rule 'price <= 15%': '%' at column 12 can be used only with discount"
+        );
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        testutils::setup_logger();
+        // given
+        let sources = [
+            "",
+            "price <=",
+            "(on_sale",
+            "price 15",
+            "discount >= 40 $",
+            "foo",
+        ];
+
+        // when
+        let results = sources.map(Rule::new);
+
+        // then
+        assert!(results.iter().all(Result::is_err));
+    }
+}
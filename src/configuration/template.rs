@@ -0,0 +1,138 @@
+use anyhow::{bail, Result};
+
+/// Text with `{{field}}` placeholders, e.g. `{{title}}: {{price}} {{currency}}`.
+///
+/// Fields are checked when the template is parsed, so a typo in the config is reported when it's
+/// loaded, not when the first notification is sent.
+#[derive(Debug, Clone)]
+pub(crate) struct Template {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Text(String),
+    Field(String),
+}
+
+impl PartialEq for Template {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Template {
+    pub(crate) fn parse(source: &str, fields: &[&str]) -> Result<Self> {
+        let mut segments = Vec::new();
+        let mut rest = source;
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                segments.push(Segment::Text(rest[..start].to_string()));
+            }
+            let column = source[..source.len() - rest.len() + start].chars().count() + 1;
+            let Some(len) = rest[start + 2..].find("}}") else {
+                bail!("unclosed '{{{{' at column {column}");
+            };
+            let field = rest[start + 2..start + 2 + len].trim();
+            if !fields.contains(&field) {
+                bail!(
+                    "unknown field '{field}' at column {column}, expected one of: {}",
+                    fields.join(", ")
+                );
+            }
+            segments.push(Segment::Field(field.to_string()));
+            rest = &rest[start + 2 + len + 2..];
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Text(rest.to_string()));
+        }
+        Ok(Self {
+            source: source.to_string(),
+            segments,
+        })
+    }
+
+    /// Replaces fields with values given by `value`.
+    pub(crate) fn render<F: Fn(&str) -> String>(&self, value: F) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => text.clone(),
+                Segment::Field(field) => value(field),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testutils;
+
+    const FIELDS: &[&str] = &["title", "price"];
+
+    #[test]
+    fn test_render() {
+        testutils::setup_logger();
+        // given
+        let template = Template::parse("- {{title}}: {{ price }} EUR", FIELDS).unwrap();
+
+        // when
+        let rendered = template.render(|field| match field {
+            "title" => "Game 1".into(),
+            _ => "9.99".into(),
+        });
+
+        // then
+        assert_eq!(rendered, "- Game 1: 9.99 EUR");
+    }
+
+    #[test]
+    fn test_parse_with_unknown_field() {
+        testutils::setup_logger();
+        // given
+        let source = "{{title}} for {{prise}}";
+
+        // when
+        let result = Template::parse(source, FIELDS);
+
+        // then
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "unknown field 'prise' at column 15, expected one of: title, price"
+        );
+    }
+
+    #[test]
+    fn test_parse_reports_column_in_chars() {
+        testutils::setup_logger();
+        // given
+        let source = "Pok\u{e9}mon \u{2014} {{prise}}";
+
+        // when
+        let result = Template::parse(source, FIELDS);
+
+        // then
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "unknown field 'prise' at column 11, expected one of: title, price"
+        );
+    }
+
+    #[test]
+    fn test_parse_with_unclosed_field() {
+        testutils::setup_logger();
+        // given
+        let source = "{{title}} for {{price";
+
+        // when
+        let result = Template::parse(source, FIELDS);
+
+        // then
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "unclosed '{{' at column 15"
+        );
+    }
+}
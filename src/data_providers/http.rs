@@ -0,0 +1,397 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use rand::Rng;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+use ureq::{Agent, AgentBuilder, ErrorKind, Request, Response};
+
+// protects from sleeping for hours when a server asks for it
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub(crate) struct HttpConfig {
+    connect_timeout_secs: u64,
+    read_timeout_secs: u64,
+    max_retries: u32,
+    initial_backoff_ms: u64,
+    max_backoff_secs: u64,
+    concurrency: usize,
+    max_requests_per_host: usize,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: 10,
+            read_timeout_secs: 30,
+            max_retries: 3,
+            initial_backoff_ms: 500,
+            max_backoff_secs: 30,
+            concurrency: 4,
+            max_requests_per_host: 2,
+        }
+    }
+}
+
+/// HTTP client retrying server errors and network failures with jittered exponential backoff.
+#[derive(Debug, Clone)]
+pub(crate) struct HttpClient {
+    agent: Agent,
+    config: HttpConfig,
+    limiter: Arc<HostLimiter>,
+}
+
+impl HttpClient {
+    pub(crate) fn new(config: HttpConfig) -> Self {
+        let agent = AgentBuilder::new()
+            .timeout_connect(Duration::from_secs(config.connect_timeout_secs))
+            .timeout_read(Duration::from_secs(config.read_timeout_secs))
+            .build();
+        let limiter = Arc::new(HostLimiter::new(config.max_requests_per_host));
+        Self {
+            agent,
+            config,
+            limiter,
+        }
+    }
+
+    /// Number of requests which can be made at the same time, to all hosts.
+    pub(crate) fn concurrency(&self) -> usize {
+        self.config.concurrency.max(1)
+    }
+
+    pub(crate) fn get(&self, url: &str) -> Request {
+        self.agent.get(url)
+    }
+
+    pub(crate) fn call(&self, request: &Request) -> Result<Response> {
+        let host = request.request_url()?.host().to_string();
+        let mut attempt = 0;
+        loop {
+            let permit = self.limiter.acquire(&host);
+            let result = request.clone().call();
+            drop(permit);
+            let error = match result {
+                Ok(response) => return Ok(response),
+                Err(error) => error,
+            };
+            if attempt >= self.config.max_retries || !is_retryable(&error) {
+                return Err(error.into());
+            }
+            let delay = requested_delay(&error).unwrap_or_else(|| self.backoff(attempt));
+            attempt += 1;
+            warn!(
+                "request to {} failed ({error}), retry {attempt} of {} in {delay:?}",
+                request.url(),
+                self.config.max_retries
+            );
+            thread::sleep(delay);
+        }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let initial = Duration::from_millis(self.config.initial_backoff_ms);
+        let max = Duration::from_secs(self.config.max_backoff_secs);
+        let backoff = initial
+            .checked_mul(2_u32.saturating_pow(attempt))
+            .map_or(max, |backoff| backoff.min(max));
+        // half of the backoff is random, so clients failing together don't retry together
+        let jitter = rand::thread_rng().gen_range(0.5..=1.0);
+        backoff.mul_f64(jitter)
+    }
+}
+
+/// Limits number of requests sent to the same host at once, so concurrent fetching doesn't
+/// hammer a single store.
+#[derive(Debug)]
+struct HostLimiter {
+    max_per_host: usize,
+    in_flight: Mutex<HashMap<String, usize>>,
+    released: Condvar,
+}
+
+impl HostLimiter {
+    fn new(max_per_host: usize) -> Self {
+        Self {
+            max_per_host: max_per_host.max(1),
+            in_flight: Mutex::new(HashMap::new()),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Blocks until a request to the host can be sent.
+    fn acquire(&self, host: &str) -> HostPermit<'_> {
+        let mut in_flight = self.in_flight.lock().expect("poisoned lock");
+        while in_flight.get(host).copied().unwrap_or(0) >= self.max_per_host {
+            debug!("waiting for a free slot for requests to {host}");
+            in_flight = self.released.wait(in_flight).expect("poisoned lock");
+        }
+        *in_flight.entry(host.to_string()).or_insert(0) += 1;
+        HostPermit {
+            limiter: self,
+            host: host.to_string(),
+        }
+    }
+
+    fn release(&self, host: &str) {
+        let mut in_flight = self.in_flight.lock().expect("poisoned lock");
+        if let Some(count) = in_flight.get_mut(host) {
+            *count -= 1;
+        }
+        self.released.notify_all();
+    }
+}
+
+/// Slot for a single request, freed on drop.
+struct HostPermit<'a> {
+    limiter: &'a HostLimiter,
+    host: String,
+}
+
+impl Drop for HostPermit<'_> {
+    fn drop(&mut self) {
+        self.limiter.release(&self.host);
+    }
+}
+
+/// Server errors, throttling and network failures are worth retrying. Other errors, e.g. not found
+/// page or invalid url, won't go away.
+fn is_retryable(error: &ureq::Error) -> bool {
+    match error {
+        ureq::Error::Status(status, _) => *status == 429 || *status >= 500,
+        ureq::Error::Transport(transport) => matches!(
+            transport.kind(),
+            ErrorKind::Dns | ErrorKind::ConnectionFailed | ErrorKind::Io
+        ),
+    }
+}
+
+/// Delay requested by the server with `Retry-After` header.
+fn requested_delay(error: &ureq::Error) -> Option<Duration> {
+    match error {
+        ureq::Error::Status(_, response) => {
+            response.header("Retry-After").and_then(parse_retry_after)
+        }
+        ureq::Error::Transport(_) => None,
+    }
+}
+
+/// Supports both formats of `Retry-After` header: number of seconds and HTTP date.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let delay = if let Ok(secs) = value.trim().parse::<u64>() {
+        Duration::from_secs(secs)
+    } else {
+        let date = DateTime::parse_from_rfc2822(value.trim()).ok()?;
+        (date.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or_default()
+    };
+    debug!("server asked to retry after {delay:?}");
+    Some(delay.min(MAX_RETRY_AFTER))
+}
+
+#[cfg(test)]
+impl HttpConfig {
+    /// Config with retries happening immediately.
+    pub(crate) fn without_delays() -> Self {
+        Self {
+            initial_backoff_ms: 0,
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testutils::{self, MockResponse, MockServer};
+    use chrono::Duration as ChronoDuration;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_call_retries_server_errors() {
+        testutils::setup_logger();
+        // given
+        let server = MockServer::start(vec![
+            MockResponse::status(503),
+            MockResponse::status(500),
+            MockResponse::ok("done"),
+        ]);
+        let client = HttpClient::new(HttpConfig::without_delays());
+
+        // when
+        let response = client.call(&client.get(&server.url("/"))).unwrap();
+
+        // then
+        assert_eq!(response.into_string().unwrap(), "done");
+        assert_eq!(server.requests_count(), 3);
+    }
+
+    #[test]
+    fn test_call_gives_up_after_max_retries() {
+        testutils::setup_logger();
+        // given
+        let server = MockServer::start(vec![MockResponse::status(503)]);
+        let client = HttpClient::new(HttpConfig::without_delays().with_max_retries(2));
+
+        // when
+        let result = client.call(&client.get(&server.url("/")));
+
+        // then
+        assert!(result.is_err());
+        assert_eq!(server.requests_count(), 3);
+    }
+
+    #[test]
+    fn test_call_does_not_retry_client_errors() {
+        testutils::setup_logger();
+        // given
+        let server = MockServer::start(vec![MockResponse::status(404)]);
+        let client = HttpClient::new(HttpConfig::without_delays());
+
+        // when
+        let result = client.call(&client.get(&server.url("/")));
+
+        // then
+        assert!(result.is_err());
+        assert_eq!(server.requests_count(), 1);
+    }
+
+    #[test]
+    fn test_call_respects_retry_after() {
+        testutils::setup_logger();
+        // given
+        let server = MockServer::start(vec![
+            MockResponse::status(429).with_header("Retry-After", "1"),
+            MockResponse::ok("done"),
+        ]);
+        let client = HttpClient::new(HttpConfig::without_delays());
+        let started = std::time::Instant::now();
+
+        // when
+        let response = client.call(&client.get(&server.url("/"))).unwrap();
+
+        // then
+        assert_eq!(response.into_string().unwrap(), "done");
+        assert!(started.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_call_fails_when_server_is_unreachable() {
+        testutils::setup_logger();
+        // given
+        let url = MockServer::unused_url();
+        let client = HttpClient::new(HttpConfig::without_delays().with_max_retries(1));
+
+        // when
+        let result = client.call(&client.get(&url));
+
+        // then
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_host_limiter_limits_requests_to_the_same_host() {
+        testutils::setup_logger();
+        // given
+        let limiter = HostLimiter::new(2);
+        let in_flight = AtomicUsize::new(0);
+        let max_in_flight = AtomicUsize::new(0);
+
+        // when
+        thread::scope(|scope| {
+            for _ in 0..6 {
+                scope.spawn(|| {
+                    let _permit = limiter.acquire("example.com");
+                    let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_in_flight.fetch_max(current, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(20));
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+
+        // then
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_host_limiter_does_not_limit_other_hosts() {
+        testutils::setup_logger();
+        // given
+        let limiter = HostLimiter::new(1);
+        let _permit = limiter.acquire("example.com");
+
+        // when
+        let _other = limiter.acquire("example.org");
+
+        // then
+        let in_flight = limiter.in_flight.lock().unwrap();
+        assert_eq!(in_flight.get("example.com"), Some(&1));
+        assert_eq!(in_flight.get("example.org"), Some(&1));
+    }
+
+    #[test]
+    fn test_parse_retry_after_with_seconds() {
+        // given
+        let value = "120";
+
+        // when
+        let delay = parse_retry_after(value);
+
+        // then
+        assert_eq!(delay, Some(Duration::from_secs(120)));
+    }
+
+    #[test]
+    fn test_parse_retry_after_with_date() {
+        // given
+        let value = (Utc::now() + ChronoDuration::seconds(60)).to_rfc2822();
+
+        // when
+        let delay = parse_retry_after(&value).unwrap();
+
+        // then
+        assert!(delay > Duration::from_secs(50) && delay <= Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_parse_retry_after_is_capped() {
+        // given
+        let value = "86400";
+
+        // when
+        let delay = parse_retry_after(value);
+
+        // then
+        assert_eq!(delay, Some(MAX_RETRY_AFTER));
+    }
+
+    #[test]
+    fn test_backoff_grows_exponentially_up_to_max() {
+        // given
+        let client = HttpClient::new(HttpConfig::default());
+
+        // when
+        let delays = (0..8)
+            .map(|attempt| client.backoff(attempt))
+            .collect::<Vec<_>>();
+
+        // then
+        assert!(delays[0] >= Duration::from_millis(250) && delays[0] <= Duration::from_millis(500));
+        assert!(
+            delays[2] >= Duration::from_millis(1000) && delays[2] <= Duration::from_millis(2000)
+        );
+        assert!(delays[7] >= Duration::from_secs(15) && delays[7] <= Duration::from_secs(30));
+    }
+}
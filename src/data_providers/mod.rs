@@ -0,0 +1,100 @@
+use crate::entities::{Region, WatchedGame};
+use crate::switch::Game;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+mod http;
+mod nintendo_america;
+mod nintendo_europe;
+mod nintendo_prices;
+
+pub(crate) use http::{HttpClient, HttpConfig};
+pub(crate) use nintendo_america::NintendoAmerica;
+pub(crate) use nintendo_europe::NintendoEurope;
+
+/// Source of games data, e.g. an online store. Games are fetched from many threads at once.
+pub(crate) trait GameProvider: Send + Sync {
+    /// Name of the provider used in logs.
+    fn name(&self) -> &'static str;
+
+    /// Regions supported by the provider, the first one is used by default.
+    fn regions(&self) -> &'static [Region];
+
+    fn capabilities(&self) -> Capabilities;
+
+    /// Max number of batches fetched at the same time.
+    fn concurrency(&self) -> usize {
+        1
+    }
+
+    /// Fetches games which can match watched game in given region. Filtering them is up to the
+    /// caller.
+    fn fetch(&self, watched_game: &WatchedGame, region: Region) -> Result<Vec<Game>>;
+
+    /// Fetches games which can match any of the watched games, all in given region. Providers
+    /// searching for many titles at once override it, see [`Capabilities::batch_size`].
+    fn fetch_batch(&self, watched_games: &[WatchedGame], region: Region) -> Result<Vec<Game>> {
+        let mut games = Vec::new();
+        for watched_game in watched_games {
+            for game in self.fetch(watched_game, region)? {
+                // searches for similar titles can find the same games
+                if !games.contains(&game) {
+                    games.push(game);
+                }
+            }
+        }
+        Ok(games)
+    }
+
+    /// Ends of current sales of games with given NSUIDs in the region. Stores which can't tell
+    /// when sales end return nothing.
+    fn sale_ends(
+        &self,
+        _nsuids: &[String],
+        _region: Region,
+    ) -> Result<HashMap<String, DateTime<Utc>>> {
+        Ok(HashMap::new())
+    }
+
+    fn default_region(&self) -> Region {
+        self.regions()[0]
+    }
+
+    /// Region of the watched game, if it's not set, provider's default is used.
+    fn region_for(&self, watched_game: &WatchedGame) -> Region {
+        watched_game
+            .region()
+            .unwrap_or_else(|| self.default_region())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Capabilities {
+    /// Provider marks games on sale explicitly. Without it, sale is deduced from prices.
+    pub(crate) sale_flag: bool,
+    /// Max number of watched games fetched with one [`GameProvider::fetch_batch`] call.
+    pub(crate) batch_size: usize,
+    /// Provider searches by store IDs and reads product codes of found games. Without it, games
+    /// are searched by the title and only their NSUIDs are known.
+    pub(crate) store_id_search: bool,
+}
+
+/// Store which can be selected in the config.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ProviderKind {
+    #[default]
+    NintendoEurope,
+    NintendoAmerica,
+}
+
+pub(crate) fn provider(kind: ProviderKind, http_config: &HttpConfig) -> Box<dyn GameProvider> {
+    let client = HttpClient::new(http_config.clone());
+    match kind {
+        ProviderKind::NintendoEurope => Box::new(NintendoEurope::new(client)),
+        ProviderKind::NintendoAmerica => Box::new(NintendoAmerica::new(client)),
+    }
+}
@@ -0,0 +1,206 @@
+use crate::data_providers::{nintendo_prices, Capabilities, GameProvider, HttpClient};
+use crate::entities::{Region, WatchedGame};
+use crate::switch::Game;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use log::debug;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+const SEARCH_URL: &str = "https://u3b6gr4ua3-dsn.algolia.net/1/indexes/store_game_en_us";
+
+const APPLICATION_ID: &str = "U3B6GR4UA3";
+
+// search-only key which nintendo.com uses in the browser
+const API_KEY: &str = "a29c6927638bfd8cee23993e51e721c9";
+
+const HITS_PER_PAGE: &str = "100";
+
+const SITE_URL: &str = "https://www.nintendo.com";
+
+const SWITCH_PLATFORM: &str = "Nintendo Switch";
+
+/// Nintendo of America store, searched with the Algolia index backing nintendo.com.
+#[derive(Debug)]
+pub(crate) struct NintendoAmerica {
+    client: HttpClient,
+}
+
+impl NintendoAmerica {
+    pub(crate) fn new(client: HttpClient) -> Self {
+        Self { client }
+    }
+}
+
+impl GameProvider for NintendoAmerica {
+    fn name(&self) -> &'static str {
+        "Nintendo of America"
+    }
+
+    fn regions(&self) -> &'static [Region] {
+        &[Region::Us]
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            sale_flag: false,
+            batch_size: 1,
+            store_id_search: false,
+        }
+    }
+
+    fn concurrency(&self) -> usize {
+        self.client.concurrency()
+    }
+
+    fn fetch(&self, watched_game: &WatchedGame, region: Region) -> Result<Vec<Game>> {
+        let request = build_request(&self.client, &watched_game.title());
+        let response = self.client.call(&request)?.into_string()?;
+        Ok(parse_games(&response)?
+            .into_iter()
+            .map(|game| game.in_region(region).with_site(SITE_URL))
+            .collect())
+    }
+
+    fn sale_ends(
+        &self,
+        nsuids: &[String],
+        region: Region,
+    ) -> Result<HashMap<String, DateTime<Utc>>> {
+        nintendo_prices::fetch_sale_ends(&self.client, nintendo_prices::PRICE_URL, nsuids, region)
+    }
+}
+
+fn build_request(client: &HttpClient, title: &str) -> ureq::Request {
+    let request = client
+        .get(SEARCH_URL)
+        .set("X-Algolia-Application-Id", APPLICATION_ID)
+        .set("X-Algolia-API-Key", API_KEY)
+        .query("query", title)
+        .query("hitsPerPage", HITS_PER_PAGE);
+    debug!("built url: {}", request.url());
+    request
+}
+
+fn parse_games(response: &str) -> Result<Vec<Game>> {
+    let response: SearchResponse = serde_json::from_str(response)?;
+    Ok(response
+        .hits
+        .into_iter()
+        .filter(Hit::is_switch_game)
+        .map(Game::from)
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct SearchResponse {
+    hits: Vec<Hit>,
+}
+
+#[derive(Deserialize)]
+struct Hit {
+    title: String,
+    nsuid: Option<String>,
+    url: Option<String>,
+    platform: Option<String>,
+    // not all entries have a price, e.g. DLCs which are sold only in bundles
+    price: Option<Price>,
+}
+
+impl Hit {
+    fn is_switch_game(&self) -> bool {
+        self.platform
+            .as_deref()
+            .is_none_or(|platform| platform == SWITCH_PLATFORM)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Price {
+    reg_price: Option<f64>,
+    sale_price: Option<f64>,
+}
+
+impl From<Hit> for Game {
+    fn from(hit: Hit) -> Self {
+        let (regular, discounted) = hit
+            .price
+            .map_or((None, None), |price| (price.reg_price, price.sale_price));
+        let mut game = Game::with_prices(hit.title, regular, discounted);
+        if let Some(nsuid) = hit.nsuid {
+            game = game.with_nsuid(nsuid);
+        }
+        if let Some(url) = hit.url {
+            game = game.with_url(url);
+        }
+        game
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data_providers::HttpConfig;
+    use crate::testutils;
+
+    const SEARCH_RESPONSE: &str = include_str!("../../tests/fixtures/nintendo_america_search.json");
+
+    #[test]
+    fn test_build_request_with_special_chars() {
+        testutils::setup_logger();
+        // given
+        let title = "Ori & the Will of the Wisps";
+        let client = HttpClient::new(HttpConfig::default());
+
+        // when
+        let request = build_request(&client, title);
+
+        // then
+        assert_eq!(
+            request.url(),
+            "https://u3b6gr4ua3-dsn.algolia.net/1/indexes/store_game_en_us\
+            ?query=Ori+%26+the+Will+of+the+Wisps&hitsPerPage=100"
+        );
+    }
+
+    #[test]
+    fn test_parse_games_from_recorded_response() {
+        testutils::setup_logger();
+        // given
+        let response = SEARCH_RESPONSE;
+
+        // when
+        let games = parse_games(response).unwrap();
+
+        // then
+        assert_eq!(
+            games,
+            vec![
+                Game::with_prices("Minecraft Dungeons", Some(19.99), Some(9.99))
+                    .with_nsuid("70010000025453")
+                    .with_url("/us/store/products/minecraft-dungeons-switch/"),
+                Game::with_prices("Minecraft Dungeons: Ultimate Edition", Some(39.99), None)
+                    .with_nsuid("70010000039165")
+                    .with_url("/us/store/products/minecraft-dungeons-ultimate-edition-switch/"),
+                Game::with_prices("Minecraft Dungeons: Hidden Depths", None, None)
+                    .with_nsuid("70070000012498")
+                    .with_url("/us/store/products/minecraft-dungeons-hidden-depths-switch/"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_games_with_invalid_response() {
+        testutils::setup_logger();
+        // given
+        let response = r#"{"message": "Invalid Application-ID or API key", "status": 403}"#;
+
+        // when
+        let games = parse_games(response);
+
+        // then
+        assert!(games.is_err());
+    }
+}
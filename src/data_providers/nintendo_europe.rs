@@ -0,0 +1,591 @@
+use crate::data_providers::{nintendo_prices, Capabilities, GameProvider, HttpClient};
+use crate::entities::{Region, WatchedGame};
+use crate::switch::Game;
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use log::debug;
+use serde::Deserialize;
+use std::collections::HashMap;
+use url::Url;
+
+const SEARCH_URL: &str = "http://search.nintendo-europe.com";
+
+// keeps the query short enough for the url
+const BATCH_SIZE: usize = 10;
+
+const PAGE_SIZE: usize = 99;
+
+const SOLR_SPECIAL_CHARS: &str = "+-&|!(){}[]^\"~*?:\\/";
+
+// broad titles, e.g. "LEGO", match hundreds of games, the cap protects from paging forever
+const MAX_PAGES: usize = 10;
+
+/// Nintendo Europe store, searched with the Solr instance backing its website.
+#[derive(Debug)]
+pub(crate) struct NintendoEurope {
+    client: HttpClient,
+    search_url: String,
+}
+
+impl NintendoEurope {
+    pub(crate) fn new(client: HttpClient) -> Self {
+        Self::with_search_url(client, SEARCH_URL)
+    }
+
+    pub(crate) fn with_search_url<S: Into<String>>(client: HttpClient, search_url: S) -> Self {
+        Self {
+            client,
+            search_url: search_url.into(),
+        }
+    }
+}
+
+impl GameProvider for NintendoEurope {
+    fn name(&self) -> &'static str {
+        "Nintendo Europe"
+    }
+
+    fn regions(&self) -> &'static [Region] {
+        &[
+            Region::En,
+            Region::De,
+            Region::Fr,
+            Region::It,
+            Region::Es,
+            Region::Nl,
+            Region::Pt,
+            Region::Pl,
+            Region::Ru,
+        ]
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            sale_flag: true,
+            batch_size: BATCH_SIZE,
+            store_id_search: true,
+        }
+    }
+
+    fn concurrency(&self) -> usize {
+        self.client.concurrency()
+    }
+
+    fn fetch(&self, watched_game: &WatchedGame, region: Region) -> Result<Vec<Game>> {
+        self.fetch_batch(std::slice::from_ref(watched_game), region)
+    }
+
+    fn fetch_batch(&self, watched_games: &[WatchedGame], region: Region) -> Result<Vec<Game>> {
+        let mut games = Vec::new();
+        for page in 0..MAX_PAGES {
+            let start = page * PAGE_SIZE;
+            let request = self
+                .client
+                .get(build_url(&self.search_url, watched_games, region, start)?.as_str());
+            let response = self.client.call(&request)?.into_json::<Root>()?.response;
+            let page_len = response.docs.len();
+            games.extend(
+                response
+                    .docs
+                    .into_iter()
+                    .map(|game| game.in_region(region).with_site(site_url(region))),
+            );
+            if page_len < PAGE_SIZE || start + page_len >= response.num_found {
+                return Ok(games);
+            }
+            debug!("fetched {} of {} games", games.len(), response.num_found);
+        }
+        let titles = watched_games
+            .iter()
+            .map(WatchedGame::title)
+            .collect::<Vec<_>>();
+        // games beyond the cap would silently look like not on sale
+        bail!(
+            "search for {titles:?} found more than {} games, refine the titles",
+            games.len()
+        )
+    }
+
+    fn sale_ends(
+        &self,
+        nsuids: &[String],
+        region: Region,
+    ) -> Result<HashMap<String, DateTime<Utc>>> {
+        nintendo_prices::fetch_sale_ends(&self.client, nintendo_prices::PRICE_URL, nsuids, region)
+    }
+}
+
+/// Regional Nintendo site which store pages from the search are relative to.
+fn site_url(region: Region) -> &'static str {
+    match region {
+        Region::De => "https://www.nintendo.de",
+        Region::Fr => "https://www.nintendo.fr",
+        Region::It => "https://www.nintendo.it",
+        Region::Es => "https://www.nintendo.es",
+        Region::Nl => "https://www.nintendo.nl",
+        Region::Pt => "https://www.nintendo.pt",
+        Region::Ru => "https://www.nintendo.ru",
+        Region::En | Region::Pl | Region::Us => "https://www.nintendo.co.uk",
+    }
+}
+
+/// Url searching for any of watched games, returning the page of results beginning at `start`.
+fn build_url(
+    search_url: &str,
+    watched_games: &[WatchedGame],
+    region: Region,
+    start: usize,
+) -> Result<Url> {
+    let (filters, queries): (Vec<_>, Vec<_>) = watched_games.iter().map(search_clauses).unzip();
+    let games_filter = filters.join(" OR ");
+    let query = queries.join(" OR ");
+
+    let mut url = Url::parse(&format!("{search_url}/{}/select", region.locale()))?;
+    url.query_pairs_mut()
+        .append_pair("rows", &PAGE_SIZE.to_string())
+        .append_pair(
+            "fq",
+            &format!(
+                "type:GAME AND system_type:nintendoswitch* AND product_code_txt:* \
+                AND ({games_filter})"
+            ),
+        )
+        .append_pair("q", &query)
+        .append_pair("sort", "sorting_title asc")
+        .append_pair("start", &start.to_string())
+        .append_pair("wt", "json");
+    debug!("built url: {url}");
+    Ok(url)
+}
+
+/// Filter and query clauses finding the watched game: by the store ID if it has one, as titles
+/// change and get reused, by the title otherwise. Titles are searched as phrases, as loose words
+/// like "the" would match many unrelated games and overflow the results of the whole batch.
+fn search_clauses(watched_game: &WatchedGame) -> (String, String) {
+    let id_clause = match (watched_game.nsuid(), watched_game.product_code()) {
+        (Some(nsuid), _) => Some(format!("nsuid_txt:({})", escape(nsuid))),
+        (None, Some(product_code)) => Some(format!("product_code_txt:({})", escape(product_code))),
+        (None, None) => None,
+    };
+    if let Some(clause) = id_clause {
+        (clause.clone(), clause)
+    } else {
+        let title = phrase(&watched_game.title());
+        (format!("title:{title}"), title)
+    }
+}
+
+/// Escapes characters with special meaning in Solr query syntax, so store IDs are searched as they
+/// are.
+fn escape(term: &str) -> String {
+    let mut escaped = String::with_capacity(term.len());
+    for c in term.chars() {
+        if SOLR_SPECIAL_CHARS.contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Quoted phrase, inside of which only quotes and backslashes have special meaning.
+fn phrase(title: &str) -> String {
+    let mut phrase = String::with_capacity(title.len() + 2);
+    phrase.push('"');
+    for c in title.chars() {
+        if matches!(c, '"' | '\\') {
+            phrase.push('\\');
+        }
+        phrase.push(c);
+    }
+    phrase.push('"');
+    phrase
+}
+
+#[derive(Deserialize)]
+struct Root {
+    response: Response,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    // missing only in broken responses, then there is nothing more to fetch
+    #[serde(rename = "numFound", default)]
+    num_found: usize,
+    docs: Vec<Game>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data_providers::HttpConfig;
+    use crate::switch::acceptable_games;
+    use crate::testutils::{self, MockResponse, MockServer};
+
+    #[test]
+    fn test_fetch_retries_server_errors() {
+        testutils::setup_logger();
+        // given
+        let server = MockServer::start(vec![
+            MockResponse::status(503),
+            MockResponse::ok(r#"{"response": {"docs": [{"title": "Game 1"}]}}"#),
+        ]);
+        let client = HttpClient::new(HttpConfig::without_delays());
+        let provider = NintendoEurope::with_search_url(client, server.url(""));
+
+        // when
+        let games = provider
+            .fetch(&WatchedGame::new("Game 1"), Region::De)
+            .unwrap();
+
+        // then
+        assert_eq!(games, vec![Game::new("Game 1").in_region(Region::De)]);
+        assert_eq!(server.requests_count(), 2);
+    }
+
+    #[test]
+    fn test_fetch_batch_sends_one_request() {
+        testutils::setup_logger();
+        // given
+        let server = MockServer::start(vec![MockResponse::ok(
+            r#"{"response": {"docs": [{"title": "Game 1"}, {"title": "Game 2"}]}}"#,
+        )]);
+        let client = HttpClient::new(HttpConfig::without_delays());
+        let provider = NintendoEurope::with_search_url(client, server.url(""));
+        let watched_games = [
+            WatchedGame::new("Game 1"),
+            WatchedGame::new("Game 2"),
+            WatchedGame::new("Game 3"),
+        ];
+
+        // when
+        let games = provider.fetch_batch(&watched_games, Region::En).unwrap();
+
+        // then
+        assert_eq!(games.len(), 2);
+        assert_eq!(server.requests_count(), 1);
+    }
+
+    #[test]
+    fn test_fetch_reads_discount_percentage_and_links() {
+        testutils::setup_logger();
+        // given
+        let server = MockServer::start(vec![MockResponse::ok(
+            r#"{"response": {"docs": [{
+                "title": "Game 1",
+                "price_regular_f": 19.99,
+                "price_discounted_f": 9.99,
+                "price_has_discount_b": true,
+                "price_discount_percentage_f": 50.0,
+                "url": "/Games/Nintendo-Switch-games/Game-1-1234.html",
+                "image_url_sq_s": "//fs-prod-cdn.nintendo-europe.com/media/images/game-1.jpg"
+            }]}}"#,
+        )]);
+        let client = HttpClient::new(HttpConfig::without_delays());
+        let provider = NintendoEurope::with_search_url(client, server.url(""));
+
+        // when
+        let games = provider
+            .fetch(&WatchedGame::new("Game 1"), Region::De)
+            .unwrap();
+
+        // then
+        assert_eq!(games[0].discount_percent(), Some(50.0));
+        assert_eq!(
+            games[0].url(),
+            Some("https://www.nintendo.de/Games/Nintendo-Switch-games/Game-1-1234.html")
+        );
+        assert_eq!(
+            games[0].image_url().as_deref(),
+            Some("https://fs-prod-cdn.nintendo-europe.com/media/images/game-1.jpg")
+        );
+    }
+
+    fn page(num_found: usize, docs: usize) -> MockResponse {
+        let docs = (0..docs)
+            .map(|idx| format!(r#"{{"title": "Game {idx}"}}"#))
+            .collect::<Vec<_>>()
+            .join(",");
+        MockResponse::ok(format!(
+            r#"{{"response": {{"numFound": {num_found}, "docs": [{docs}]}}}}"#
+        ))
+    }
+
+    #[test]
+    fn test_fetch_follows_pages() {
+        testutils::setup_logger();
+        // given
+        let server = MockServer::start(vec![page(150, 99), page(150, 51)]);
+        let client = HttpClient::new(HttpConfig::without_delays());
+        let provider = NintendoEurope::with_search_url(client, server.url(""));
+
+        // when
+        let games = provider
+            .fetch(&WatchedGame::new("Game"), Region::En)
+            .unwrap();
+
+        // then
+        assert_eq!(games.len(), 150);
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].contains("&start=0&"));
+        assert!(requests[1].contains("&start=99&"));
+    }
+
+    #[test]
+    fn test_fetch_fails_at_max_pages() {
+        testutils::setup_logger();
+        // given
+        let server = MockServer::start(vec![page(100_000, 99)]);
+        let client = HttpClient::new(HttpConfig::without_delays());
+        let provider = NintendoEurope::with_search_url(client, server.url(""));
+
+        // when
+        let result = provider.fetch(&WatchedGame::new("Game"), Region::En);
+
+        // then
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            format!(
+                "search for [\"Game\"] found more than {} games, refine the titles",
+                MAX_PAGES * PAGE_SIZE
+            )
+        );
+        assert_eq!(server.requests_count(), MAX_PAGES);
+    }
+
+    #[test]
+    fn test_acceptable_games_are_fetched_in_batches() {
+        testutils::setup_logger();
+        // given
+        let server = MockServer::start(vec![MockResponse::ok(
+            r#"{"response": {"docs": [
+                {"title": "Game 1", "price_has_discount_b": true},
+                {"title": "Game 12", "price_has_discount_b": true}
+            ]}}"#,
+        )]);
+        let client = HttpClient::new(HttpConfig::without_delays());
+        let provider = NintendoEurope::with_search_url(client, server.url(""));
+        let watched_games = (0..25)
+            .map(|idx| WatchedGame::new(format!("Game {idx}")))
+            .collect::<Vec<_>>();
+
+        // when
+        let result = acceptable_games(&watched_games, &provider);
+
+        // then
+        assert_eq!(server.requests_count(), 3);
+        let titles = result.games().iter().map(Game::title).collect::<Vec<_>>();
+        assert_eq!(titles, vec!["Game 1", "Game 12"]);
+    }
+
+    fn query_param(url: &Url, name: &str) -> String {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_build_url_with_space() {
+        testutils::setup_logger();
+        // given
+        let title = "Test title";
+
+        // when
+        let url = build_url(SEARCH_URL, &[WatchedGame::new(title)], Region::En, 0).unwrap();
+
+        // then
+        assert_eq!(
+            url.as_str(),
+            "http://search.nintendo-europe.com/en/select?rows=99\
+            &fq=type%3AGAME+AND+system_type%3Anintendoswitch*+AND+product_code_txt%3A*\
+            +AND+%28title%3A%22Test+title%22%29&q=%22Test+title%22\
+            &sort=sorting_title+asc&start=0&wt=json"
+        );
+    }
+
+    #[test]
+    fn test_build_url_with_colon() {
+        testutils::setup_logger();
+        // given
+        let title = "Alien: Isolation";
+
+        // when
+        let url = build_url(SEARCH_URL, &[WatchedGame::new(title)], Region::En, 0).unwrap();
+
+        // then
+        assert_eq!(
+            url.as_str(),
+            "http://search.nintendo-europe.com/en/select?rows=99\
+            &fq=type%3AGAME+AND+system_type%3Anintendoswitch*+AND+product_code_txt%3A*\
+            +AND+%28title%3A%22Alien%3A+Isolation%22%29&q=%22Alien%3A+Isolation%22\
+            &sort=sorting_title+asc&start=0&wt=json"
+        );
+    }
+
+    #[test]
+    fn test_build_url_with_ampersand() {
+        testutils::setup_logger();
+        // given
+        let title = "Ori & the Will of the Wisps";
+
+        // when
+        let url = build_url(SEARCH_URL, &[WatchedGame::new(title)], Region::En, 0).unwrap();
+
+        // then
+        assert_eq!(url.query_pairs().count(), 6);
+        assert_eq!(query_param(&url, "q"), "\"Ori & the Will of the Wisps\"");
+        assert_eq!(
+            url.as_str(),
+            "http://search.nintendo-europe.com/en/select?rows=99\
+            &fq=type%3AGAME+AND+system_type%3Anintendoswitch*+AND+product_code_txt%3A*\
+            +AND+%28title%3A%22Ori+%26+the+Will+of+the+Wisps%22%29\
+            &q=%22Ori+%26+the+Will+of+the+Wisps%22&sort=sorting_title+asc&start=0&wt=json"
+        );
+    }
+
+    #[test]
+    fn test_build_url_with_special_chars() {
+        testutils::setup_logger();
+        // given
+        let title = "Test!@#$%^&*()-=[]\\;',./<>?:\"{}|+_";
+
+        // when
+        let url = build_url(SEARCH_URL, &[WatchedGame::new(title)], Region::En, 0).unwrap();
+
+        // then
+        assert_eq!(url.query_pairs().count(), 6);
+        assert_eq!(
+            query_param(&url, "fq"),
+            "type:GAME AND system_type:nintendoswitch* AND product_code_txt:* \
+            AND (title:\"Test!@#$%^&*()-=[]\\\\;',./<>?:\\\"{}|+_\")"
+        );
+        assert_eq!(
+            query_param(&url, "q"),
+            "\"Test!@#$%^&*()-=[]\\\\;',./<>?:\\\"{}|+_\""
+        );
+    }
+
+    #[test]
+    fn test_build_url_with_region() {
+        testutils::setup_logger();
+        // given
+        let title = "Test title";
+
+        // when
+        let url = build_url(SEARCH_URL, &[WatchedGame::new(title)], Region::De, 0).unwrap();
+
+        // then
+        assert_eq!(
+            url.as_str(),
+            "http://search.nintendo-europe.com/de/select?rows=99\
+            &fq=type%3AGAME+AND+system_type%3Anintendoswitch*+AND+product_code_txt%3A*\
+            +AND+%28title%3A%22Test+title%22%29&q=%22Test+title%22\
+            &sort=sorting_title+asc&start=0&wt=json"
+        );
+    }
+
+    #[test]
+    fn test_build_url_with_many_titles() {
+        testutils::setup_logger();
+        // given
+        let watched_games = [WatchedGame::new("Game 1"), WatchedGame::new("Game 2")];
+
+        // when
+        let url = build_url(SEARCH_URL, &watched_games, Region::En, 99).unwrap();
+
+        // then
+        assert_eq!(
+            query_param(&url, "fq"),
+            "type:GAME AND system_type:nintendoswitch* AND product_code_txt:* \
+            AND (title:\"Game 1\" OR title:\"Game 2\")"
+        );
+        assert_eq!(query_param(&url, "q"), "\"Game 1\" OR \"Game 2\"");
+        assert_eq!(query_param(&url, "start"), "99");
+    }
+
+    #[test]
+    fn test_build_url_with_multi_word_titles_in_batch() {
+        testutils::setup_logger();
+        // given
+        let watched_games = [
+            WatchedGame::new("Hollow Knight"),
+            WatchedGame::new("Ori and the Will of the Wisps"),
+            WatchedGame::new("The Legend of Zelda: Tears of the Kingdom"),
+        ];
+
+        // when
+        let url = build_url(SEARCH_URL, &watched_games, Region::En, 0).unwrap();
+
+        // then
+        assert_eq!(
+            query_param(&url, "fq"),
+            "type:GAME AND system_type:nintendoswitch* AND product_code_txt:* \
+            AND (title:\"Hollow Knight\" OR title:\"Ori and the Will of the Wisps\" \
+            OR title:\"The Legend of Zelda: Tears of the Kingdom\")"
+        );
+        assert_eq!(
+            query_param(&url, "q"),
+            "\"Hollow Knight\" OR \"Ori and the Will of the Wisps\" \
+            OR \"The Legend of Zelda: Tears of the Kingdom\""
+        );
+    }
+
+    #[test]
+    fn test_build_url_with_store_ids() {
+        testutils::setup_logger();
+        // given
+        let watched_games = [
+            WatchedGame::new("Game 1").with_nsuid("70010000000001"),
+            WatchedGame::new("Game 2").with_product_code("HACPAAAAA"),
+            WatchedGame::new("Game 3"),
+        ];
+
+        // when
+        let url = build_url(SEARCH_URL, &watched_games, Region::En, 0).unwrap();
+
+        // then
+        assert_eq!(
+            query_param(&url, "fq"),
+            "type:GAME AND system_type:nintendoswitch* AND product_code_txt:* \
+            AND (nsuid_txt:(70010000000001) OR product_code_txt:(HACPAAAAA) OR title:\"Game 3\")"
+        );
+        assert_eq!(
+            query_param(&url, "q"),
+            "nsuid_txt:(70010000000001) OR product_code_txt:(HACPAAAAA) OR \"Game 3\""
+        );
+    }
+
+    #[test]
+    fn test_build_url_with_empty_title() {
+        testutils::setup_logger();
+        // given
+        let title = "";
+
+        // when
+        let url = build_url(SEARCH_URL, &[WatchedGame::new(title)], Region::En, 0).unwrap();
+
+        // then
+        assert_eq!(
+            url.as_str(),
+            "http://search.nintendo-europe.com/en/select?rows=99\
+            &fq=type%3AGAME+AND+system_type%3Anintendoswitch*+AND+product_code_txt%3A*\
+            +AND+%28title%3A%22%22%29&q=%22%22&sort=sorting_title+asc&start=0&wt=json"
+        );
+    }
+
+    #[test]
+    fn test_build_url_with_invalid_search_url() {
+        testutils::setup_logger();
+        // given
+        let search_url = "not a url";
+
+        // when
+        let url = build_url(search_url, &[WatchedGame::new("Test title")], Region::En, 0);
+
+        // then
+        assert!(url.is_err());
+    }
+}
@@ -0,0 +1,119 @@
+use crate::data_providers::HttpClient;
+use crate::entities::Region;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use log::debug;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// eShop price API shared by all Nintendo stores. Search indexes don't tell when sales end, it does.
+pub(crate) const PRICE_URL: &str = "https://api.ec.nintendo.com/v1/price";
+
+// max number of ids the API accepts in one request
+const MAX_IDS: usize = 50;
+
+/// Ends of current sales of games with given NSUIDs. Games which aren't on sale are left out.
+pub(crate) fn fetch_sale_ends(
+    client: &HttpClient,
+    price_url: &str,
+    nsuids: &[String],
+    region: Region,
+) -> Result<HashMap<String, DateTime<Utc>>> {
+    let mut sale_ends = HashMap::new();
+    for ids in nsuids.chunks(MAX_IDS) {
+        let request = client
+            .get(price_url)
+            .query("country", region.country())
+            .query("lang", "en")
+            .query("ids", &ids.join(","));
+        debug!("built url: {}", request.url());
+        let response = client.call(&request)?.into_string()?;
+        sale_ends.extend(parse_sale_ends(&response)?);
+    }
+    Ok(sale_ends)
+}
+
+fn parse_sale_ends(response: &str) -> Result<HashMap<String, DateTime<Utc>>> {
+    let response: PriceResponse = serde_json::from_str(response)?;
+    Ok(response
+        .prices
+        .into_iter()
+        .filter_map(|price| {
+            let end = price.discount_price?.end_datetime?;
+            Some((price.title_id.to_string(), end))
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct PriceResponse {
+    prices: Vec<TitlePrice>,
+}
+
+#[derive(Deserialize)]
+struct TitlePrice {
+    title_id: u64,
+    discount_price: Option<DiscountPrice>,
+}
+
+#[derive(Deserialize)]
+struct DiscountPrice {
+    end_datetime: Option<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data_providers::HttpConfig;
+    use crate::testutils::{self, MockResponse, MockServer};
+    use chrono::TimeZone;
+
+    const PRICE_RESPONSE: &str = include_str!("../../tests/fixtures/nintendo_price.json");
+
+    #[test]
+    fn test_fetch_sale_ends_from_recorded_response() {
+        testutils::setup_logger();
+        // given
+        let server = MockServer::start(vec![MockResponse::ok(PRICE_RESPONSE)]);
+        let client = HttpClient::new(HttpConfig::without_delays());
+        let nsuids = vec!["70010000025453".to_string(), "70010000039165".to_string()];
+
+        // when
+        let sale_ends =
+            fetch_sale_ends(&client, &server.url("/v1/price"), &nsuids, Region::De).unwrap();
+
+        // then
+        assert_eq!(
+            sale_ends,
+            HashMap::from([(
+                "70010000025453".to_string(),
+                Utc.with_ymd_and_hms(2022, 12, 15, 12, 0, 0).unwrap()
+            )])
+        );
+        assert_eq!(
+            server.requests(),
+            vec!["/v1/price?country=DE&lang=en&ids=70010000025453%2C70010000039165"]
+        );
+    }
+
+    #[test]
+    fn test_fetch_sale_ends_in_chunks() {
+        testutils::setup_logger();
+        // given
+        let empty = r#"{"prices": []}"#;
+        let server = MockServer::start(vec![MockResponse::ok(empty), MockResponse::ok(empty)]);
+        let client = HttpClient::new(HttpConfig::without_delays());
+        let nsuids = (0..=MAX_IDS)
+            .map(|i| format!("7001000000{i:04}"))
+            .collect::<Vec<_>>();
+
+        // when
+        let sale_ends =
+            fetch_sale_ends(&client, &server.url("/v1/price"), &nsuids, Region::En).unwrap();
+
+        // then
+        assert!(sale_ends.is_empty());
+        assert_eq!(server.requests_count(), 2);
+    }
+}
@@ -1,9 +1,119 @@
-use serde::Deserialize;
+use crate::configuration::Rule;
+use crate::matching::TitlePattern;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const DEFAULT_MIN_SIMILARITY: f64 = 0.9;
 
 #[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(try_from = "RawWatchedGame")]
 pub struct WatchedGame {
+    /// Title searched in the store. If not given, it's derived from the pattern or the store ID.
     title: String,
+    pattern: Option<TitlePattern>,
+    nsuid: Option<String>,
+    product_code: Option<String>,
     acceptable_price: Option<f64>,
+    min_discount_percent: Option<f64>,
+    rule: Option<Rule>,
+    notify_on_price_drop: bool,
+    criteria: Criteria,
+    /// How far above the lowest recorded price, in percent, the price may be.
+    max_percent_above_low: Option<f64>,
+    region: Option<Region>,
+    title_match: TitleMatch,
+    min_similarity: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct RawWatchedGame {
+    title: Option<String>,
+    pattern: Option<String>,
+    #[serde(default)]
+    pattern_syntax: PatternSyntax,
+    nsuid: Option<String>,
+    product_code: Option<String>,
+    acceptable_price: Option<f64>,
+    min_discount_percent: Option<f64>,
+    rule: Option<Rule>,
+    #[serde(default)]
+    notify_on_price_drop: bool,
+    #[serde(default)]
+    criteria: Criteria,
+    #[serde(default)]
+    only_all_time_low: bool,
+    within_percent_of_low: Option<f64>,
+    region: Option<Region>,
+    #[serde(rename = "match", default)]
+    title_match: TitleMatch,
+    min_similarity: Option<f64>,
+}
+
+impl TryFrom<RawWatchedGame> for WatchedGame {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: RawWatchedGame) -> Result<Self> {
+        let pattern = raw
+            .pattern
+            .map(|pattern| TitlePattern::new(&pattern, raw.pattern_syntax))
+            .transpose()?;
+        let title = match (raw.title, &pattern) {
+            (Some(title), _) => title,
+            (None, Some(pattern)) => pattern.search_title()?,
+            (None, None) => match raw.nsuid.as_ref().or(raw.product_code.as_ref()) {
+                Some(id) => id.clone(),
+                None => bail!("watched game needs a 'title', 'pattern', 'nsuid' or 'product_code'"),
+            },
+        };
+        let has_store_id = raw.nsuid.is_some() || raw.product_code.is_some();
+        // stores are searched by the title, without letters or digits it finds nothing
+        if !has_store_id && !title.chars().any(char::is_alphanumeric) {
+            bail!("title '{title}' has to contain a letter or a digit to be searched for");
+        }
+        if let Some(min_similarity) = raw.min_similarity {
+            if !(0.0..=1.0).contains(&min_similarity) {
+                bail!(
+                    "min_similarity of '{title}' has to be between 0 and 1, got {min_similarity}"
+                );
+            }
+        }
+        if let Some(min_discount_percent) = raw.min_discount_percent {
+            if !(0.0..=100.0).contains(&min_discount_percent) {
+                bail!(
+                    "min_discount_percent of '{title}' has to be between 0 and 100, \
+                    got {min_discount_percent}"
+                );
+            }
+        }
+        if let Some(within_percent_of_low) = raw.within_percent_of_low {
+            if !(0.0..=100.0).contains(&within_percent_of_low) {
+                bail!(
+                    "within_percent_of_low of '{title}' has to be between 0 and 100, \
+                    got {within_percent_of_low}"
+                );
+            }
+        }
+        let max_percent_above_low = raw
+            .within_percent_of_low
+            .or(raw.only_all_time_low.then_some(0.0));
+        Ok(Self {
+            title,
+            pattern,
+            nsuid: raw.nsuid,
+            product_code: raw.product_code,
+            acceptable_price: raw.acceptable_price,
+            min_discount_percent: raw.min_discount_percent,
+            rule: raw.rule,
+            notify_on_price_drop: raw.notify_on_price_drop,
+            criteria: raw.criteria,
+            max_percent_above_low,
+            region: raw.region,
+            title_match: raw.title_match,
+            min_similarity: raw.min_similarity,
+        })
+    }
 }
 
 impl WatchedGame {
@@ -11,21 +121,268 @@ impl WatchedGame {
     pub fn new<S: Into<String>>(title: S) -> Self {
         Self {
             title: title.into(),
+            pattern: None,
+            nsuid: None,
+            product_code: None,
             acceptable_price: None,
+            min_discount_percent: None,
+            rule: None,
+            notify_on_price_drop: false,
+            criteria: Criteria::default(),
+            max_percent_above_low: None,
+            region: None,
+            title_match: TitleMatch::default(),
+            min_similarity: None,
         }
     }
 
+    #[cfg(test)]
+    pub fn with_pattern(pattern: &str, syntax: PatternSyntax) -> Self {
+        let pattern = TitlePattern::new(pattern, syntax).expect("invalid pattern");
+        Self {
+            pattern: Some(pattern.clone()),
+            ..Self::new(pattern.search_title().expect("no title in pattern"))
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_nsuid<S: Into<String>>(mut self, nsuid: S) -> Self {
+        self.nsuid = Some(nsuid.into());
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_product_code<S: Into<String>>(mut self, product_code: S) -> Self {
+        self.product_code = Some(product_code.into());
+        self
+    }
+
     #[cfg(test)]
     pub fn with_acceptable_price(mut self, price: f64) -> Self {
         self.acceptable_price = Some(price);
         self
     }
 
+    #[cfg(test)]
+    pub fn with_min_discount_percent(mut self, percent: f64) -> Self {
+        self.min_discount_percent = Some(percent);
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_rule(mut self, rule: &str) -> Self {
+        self.rule = Some(Rule::new(rule).expect("invalid rule"));
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_notify_on_price_drop(mut self) -> Self {
+        self.notify_on_price_drop = true;
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_criteria(mut self, criteria: Criteria) -> Self {
+        self.criteria = criteria;
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_max_percent_above_low(mut self, percent: f64) -> Self {
+        self.max_percent_above_low = Some(percent);
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_region(mut self, region: Region) -> Self {
+        self.region = Some(region);
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_title_match(mut self, title_match: TitleMatch) -> Self {
+        self.title_match = title_match;
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_min_similarity(mut self, min_similarity: f64) -> Self {
+        self.min_similarity = Some(min_similarity);
+        self
+    }
+
+    /// Uses given region unless the game has its own.
+    pub(crate) fn or_region(mut self, region: Option<Region>) -> Self {
+        self.region = self.region.or(region);
+        self
+    }
+
     pub fn title(&self) -> String {
         self.title.clone()
     }
 
+    pub(crate) fn pattern(&self) -> Option<&TitlePattern> {
+        self.pattern.as_ref()
+    }
+
+    /// Nintendo eShop ID, e.g. `70010000025453`.
+    pub fn nsuid(&self) -> Option<&str> {
+        self.nsuid.as_deref()
+    }
+
+    /// Product code, e.g. `HACPAURNA`.
+    pub fn product_code(&self) -> Option<&str> {
+        self.product_code.as_deref()
+    }
+
+    /// Game is identified by store ID instead of the title.
+    pub fn has_store_id(&self) -> bool {
+        self.nsuid.is_some() || self.product_code.is_some()
+    }
+
+    /// Neither title nor pattern is set, so the store is searched for the store ID.
+    pub(crate) fn is_searched_by_store_id(&self) -> bool {
+        let title = Some(self.title.as_str());
+        self.pattern.is_none() && (self.nsuid.as_deref() == title || self.product_code() == title)
+    }
+
     pub fn acceptable_price(&self) -> Option<f64> {
         self.acceptable_price
     }
+
+    pub fn min_discount_percent(&self) -> Option<f64> {
+        self.min_discount_percent
+    }
+
+    pub(crate) fn rule(&self) -> Option<&Rule> {
+        self.rule.as_ref()
+    }
+
+    /// Price lower than in the previous check is a criterion.
+    pub fn notify_on_price_drop(&self) -> bool {
+        self.notify_on_price_drop
+    }
+
+    pub fn criteria(&self) -> Criteria {
+        self.criteria
+    }
+
+    /// Set by `only_all_time_low` (0%) or `within_percent_of_low`.
+    pub fn max_percent_above_low(&self) -> Option<f64> {
+        self.max_percent_above_low
+    }
+
+    pub fn region(&self) -> Option<Region> {
+        self.region
+    }
+
+    pub fn title_match(&self) -> TitleMatch {
+        self.title_match
+    }
+
+    /// Similarity of titles, from 0 to 1, required by fuzzy matching.
+    pub fn min_similarity(&self) -> f64 {
+        self.min_similarity.unwrap_or(DEFAULT_MIN_SIMILARITY)
+    }
+}
+
+/// How price criteria of a watched game, like `acceptable_price`, `min_discount_percent` and
+/// `rule`, are combined.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Criteria {
+    /// Game has to meet all of them.
+    #[default]
+    All,
+    /// Game has to meet at least one of them.
+    Any,
+}
+
+/// Syntax of the title pattern.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PatternSyntax {
+    /// `*` matches any text and `?` any single character, letter case is ignored.
+    #[default]
+    Glob,
+    /// Regular expression, matching anywhere in the title unless anchored.
+    Regex,
+}
+
+/// How titles of found games are compared with the title of watched game.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TitleMatch {
+    /// Titles have to be equal.
+    #[default]
+    Exact,
+    /// Titles have to be equal, ignoring case, accents, punctuation and trademark symbols.
+    Normalized,
+    /// Normalized titles have to be similar at least as much as `min_similarity` says.
+    Fuzzy,
+}
+
+/// Store region, named after the locale used by the store.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Region {
+    En,
+    De,
+    Fr,
+    It,
+    Es,
+    Nl,
+    Pt,
+    Pl,
+    Ru,
+    Us,
+}
+
+impl Region {
+    pub fn locale(self) -> &'static str {
+        match self {
+            Region::En => "en",
+            Region::De => "de",
+            Region::Fr => "fr",
+            Region::It => "it",
+            Region::Es => "es",
+            Region::Nl => "nl",
+            Region::Pt => "pt",
+            Region::Pl => "pl",
+            Region::Ru => "ru",
+            Region::Us => "us",
+        }
+    }
+
+    pub fn currency(self) -> &'static str {
+        match self {
+            Region::En => "GBP",
+            Region::De | Region::Fr | Region::It | Region::Es | Region::Nl | Region::Pt => "EUR",
+            Region::Pl => "PLN",
+            Region::Ru => "RUB",
+            Region::Us => "USD",
+        }
+    }
+
+    /// Country code used by the eShop.
+    pub fn country(self) -> &'static str {
+        match self {
+            Region::En => "GB",
+            Region::De => "DE",
+            Region::Fr => "FR",
+            Region::It => "IT",
+            Region::Es => "ES",
+            Region::Nl => "NL",
+            Region::Pt => "PT",
+            Region::Pl => "PL",
+            Region::Ru => "RU",
+            Region::Us => "US",
+        }
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.locale())
+    }
 }
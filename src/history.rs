@@ -0,0 +1,501 @@
+use crate::configuration::sweetch_dir;
+use crate::data_providers::{Capabilities, GameProvider};
+use crate::entities::{Region, WatchedGame};
+use crate::json_file::tmp_path;
+use crate::switch::{is_watched_game, Game};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::slice;
+use std::sync::{Mutex, OnceLock};
+
+const COMPACTION_THRESHOLD_BYTES: u64 = 1024 * 1024;
+
+pub(crate) fn history_path() -> PathBuf {
+    sweetch_dir().join("price_history.jsonl")
+}
+
+/// Game title with the region of the store.
+pub(crate) type PriceKey = (String, Option<Region>);
+
+/// Prices recorded for a game in previous runs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct RecordedPrices {
+    pub(crate) lowest: f64,
+    pub(crate) last: f64,
+}
+
+/// Single observation of a game price.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub(crate) struct PriceRecord {
+    pub(crate) title: String,
+    pub(crate) region: Option<Region>,
+    pub(crate) price_regular: Option<f64>,
+    pub(crate) price_discounted: Option<f64>,
+    pub(crate) has_discount: Option<bool>,
+    pub(crate) timestamp: DateTime<Utc>,
+}
+
+impl PriceRecord {
+    fn new(game: &Game, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            title: game.title(),
+            region: game.region(),
+            price_regular: game.price_regular(),
+            price_discounted: game.price_discounted(),
+            has_discount: game.has_discount(),
+            timestamp,
+        }
+    }
+
+    fn key(&self) -> (&str, Option<Region>) {
+        (&self.title, self.region)
+    }
+
+    fn lowest_price(&self) -> Option<f64> {
+        match (self.price_regular, self.price_discounted) {
+            (Some(regular), Some(discounted)) => Some(regular.min(discounted)),
+            (regular, discounted) => regular.or(discounted),
+        }
+    }
+
+    fn same_price(&self, other: &PriceRecord) -> bool {
+        self.price_regular == other.price_regular
+            && self.price_discounted == other.price_discounted
+            && self.has_discount == other.has_discount
+    }
+}
+
+/// Append-only store of all fetched prices, kept as JSON lines.
+#[derive(Debug)]
+pub(crate) struct PriceHistory {
+    path: PathBuf,
+}
+
+impl PriceHistory {
+    pub(crate) fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub(crate) fn record(&self, games: &[Game]) -> Result<()> {
+        self.record_at(games, Utc::now())
+    }
+
+    fn record_at(&self, games: &[Game], timestamp: DateTime<Utc>) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let mut writer = BufWriter::new(file);
+        for game in games {
+            serde_json::to_writer(&mut writer, &PriceRecord::new(game, timestamp))?;
+            writeln!(writer)?;
+        }
+        writer.flush()?;
+        debug!("recorded {} prices in {}", games.len(), self.path.display());
+        Ok(())
+    }
+
+    /// All records in the order they were stored. Lines which can't be parsed, e.g. because of
+    /// interrupted write, are skipped.
+    pub(crate) fn records(&self) -> Result<Vec<PriceRecord>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let reader = BufReader::new(File::open(&self.path)?);
+        let mut records = Vec::new();
+        for (idx, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(record) => records.push(record),
+                Err(e) => warn!("skipping invalid price record at line {}: {e}", idx + 1),
+            }
+        }
+        Ok(records)
+    }
+
+    /// Lowest price ever recorded and the most recent one, for each game in each region.
+    pub(crate) fn recorded_prices(&self) -> Result<HashMap<PriceKey, RecordedPrices>> {
+        let mut recorded_prices = HashMap::new();
+        for record in self.records()? {
+            let Some(price) = record.lowest_price() else {
+                continue;
+            };
+            recorded_prices
+                .entry((record.title, record.region))
+                .and_modify(|prices: &mut RecordedPrices| {
+                    prices.lowest = prices.lowest.min(price);
+                    prices.last = price;
+                })
+                .or_insert(RecordedPrices {
+                    lowest: price,
+                    last: price,
+                });
+        }
+        Ok(recorded_prices)
+    }
+
+    /// Removes records which don't bring new information - those with the same price as the
+    /// previous record of the same game.
+    pub(crate) fn compact(&self) -> Result<()> {
+        let records = self.records()?;
+        let mut last_seen: HashMap<(&str, Option<Region>), &PriceRecord> = HashMap::new();
+        let mut compacted = Vec::new();
+        for record in &records {
+            let changed = last_seen
+                .get(&record.key())
+                .is_none_or(|previous| !previous.same_price(record));
+            if changed {
+                compacted.push(record);
+            }
+            last_seen.insert(record.key(), record);
+        }
+        info!(
+            "compacting price history from {} to {} records",
+            records.len(),
+            compacted.len()
+        );
+        self.rewrite(&compacted)
+    }
+
+    pub(crate) fn compact_if_needed(&self) -> Result<()> {
+        let size = fs::metadata(&self.path).map_or(0, |metadata| metadata.len());
+        if size > COMPACTION_THRESHOLD_BYTES {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    fn rewrite(&self, records: &[&PriceRecord]) -> Result<()> {
+        let tmp_path = tmp_path(&self.path);
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        for record in records {
+            serde_json::to_writer(&mut writer, record)?;
+            writeln!(writer)?;
+        }
+        writer.flush()?;
+        fs::rename(tmp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+/// Provider which stores prices of all fetched games in the price history and annotates them with
+/// the lowest and the last price recorded before.
+pub(crate) struct RecordingProvider<'a> {
+    inner: Box<dyn GameProvider>,
+    history: &'a PriceHistory,
+    // games fetched concurrently are recorded one batch at a time, so lines don't interleave
+    recording: Mutex<()>,
+    // read once, so all games of a run are compared with prices from previous runs
+    recorded_prices: OnceLock<HashMap<PriceKey, RecordedPrices>>,
+}
+
+impl<'a> RecordingProvider<'a> {
+    pub(crate) fn new(inner: Box<dyn GameProvider>, history: &'a PriceHistory) -> Self {
+        Self {
+            inner,
+            history,
+            recording: Mutex::new(()),
+            recorded_prices: OnceLock::new(),
+        }
+    }
+}
+
+impl GameProvider for RecordingProvider<'_> {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn regions(&self) -> &'static [Region] {
+        self.inner.regions()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.inner.capabilities()
+    }
+
+    fn concurrency(&self) -> usize {
+        self.inner.concurrency()
+    }
+
+    fn fetch(&self, watched_game: &WatchedGame, region: Region) -> Result<Vec<Game>> {
+        let games = self.annotate(self.inner.fetch(watched_game, region)?);
+        self.record(&games, slice::from_ref(watched_game));
+        Ok(games)
+    }
+
+    fn fetch_batch(&self, watched_games: &[WatchedGame], region: Region) -> Result<Vec<Game>> {
+        let games = self.annotate(self.inner.fetch_batch(watched_games, region)?);
+        self.record(&games, watched_games);
+        Ok(games)
+    }
+
+    fn sale_ends(
+        &self,
+        nsuids: &[String],
+        region: Region,
+    ) -> Result<HashMap<String, DateTime<Utc>>> {
+        self.inner.sale_ends(nsuids, region)
+    }
+}
+
+impl RecordingProvider<'_> {
+    fn annotate(&self, games: Vec<Game>) -> Vec<Game> {
+        let recorded_prices = self.recorded_prices.get_or_init(|| {
+            self.history.recorded_prices().unwrap_or_else(|e| {
+                error!("failed to read recorded prices: {e:?}");
+                HashMap::new()
+            })
+        });
+        games
+            .into_iter()
+            .map(|game| {
+                let prices = recorded_prices.get(&(game.title(), game.region()));
+                game.with_lowest_recorded_price(prices.map(|prices| prices.lowest))
+                    .with_last_recorded_price(prices.map(|prices| prices.last))
+            })
+            .collect()
+    }
+
+    /// Records only watched games, other search results would just grow the history.
+    fn record(&self, games: &[Game], watched_games: &[WatchedGame]) {
+        let watched = games
+            .iter()
+            .filter(|game| {
+                watched_games
+                    .iter()
+                    .any(|watched_game| is_watched_game(game, watched_game))
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        let _recording = self.recording.lock().expect("poisoned lock");
+        // missing history entry is not a reason to skip the notification
+        if let Err(e) = self.history.record(&watched) {
+            error!("failed to record prices: {e:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testutils::{self, day, StubProvider};
+    use tempfile::TempDir;
+
+    fn history_in(dir: &TempDir) -> PriceHistory {
+        PriceHistory::new(dir.path().join("price_history.jsonl"))
+    }
+
+    #[test]
+    fn test_records_of_empty_history() {
+        testutils::setup_logger();
+        // given
+        let dir = TempDir::new().unwrap();
+        let history = history_in(&dir);
+
+        // when
+        let records = history.records().unwrap();
+
+        // then
+        assert_eq!(records, vec![]);
+    }
+
+    #[test]
+    fn test_record_appends_prices() {
+        testutils::setup_logger();
+        // given
+        let dir = TempDir::new().unwrap();
+        let history = history_in(&dir);
+        let game = Game::with_prices("Game 1", Some(10.0), Some(5.0)).in_region(Region::De);
+
+        // when
+        history.record_at(slice::from_ref(&game), day(1)).unwrap();
+        history.record_at(&[game], day(2)).unwrap();
+
+        // then
+        let record = PriceRecord {
+            title: "Game 1".into(),
+            region: Some(Region::De),
+            price_regular: Some(10.0),
+            price_discounted: Some(5.0),
+            has_discount: None,
+            timestamp: day(1),
+        };
+        assert_eq!(
+            history.records().unwrap(),
+            vec![
+                record.clone(),
+                PriceRecord {
+                    timestamp: day(2),
+                    ..record
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_records_skips_invalid_lines() {
+        testutils::setup_logger();
+        // given
+        let dir = TempDir::new().unwrap();
+        let history = history_in(&dir);
+        history
+            .record_at(&[Game::with_prices("Game 1", Some(10.0), None)], day(1))
+            .unwrap();
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(dir.path().join("price_history.jsonl"))
+            .unwrap();
+        write!(file, "{{\"title\": \"Game").unwrap();
+
+        // when
+        let records = history.records().unwrap();
+
+        // then
+        assert_eq!(records.len(), 1);
+    }
+
+    #[test]
+    fn test_compact_keeps_only_price_changes() {
+        testutils::setup_logger();
+        // given
+        let dir = TempDir::new().unwrap();
+        let history = history_in(&dir);
+        let regular = Game::with_prices("Game 1", Some(10.0), None);
+        let discounted = Game::with_prices("Game 1", Some(10.0), Some(5.0));
+        let other = Game::with_prices("Game 2", Some(10.0), None);
+        history
+            .record_at(&[regular.clone(), other.clone()], day(1))
+            .unwrap();
+        history
+            .record_at(&[regular.clone(), other], day(2))
+            .unwrap();
+        history
+            .record_at(slice::from_ref(&discounted), day(3))
+            .unwrap();
+        history.record_at(&[discounted], day(4)).unwrap();
+        history.record_at(&[regular], day(5)).unwrap();
+
+        // when
+        history.compact().unwrap();
+
+        // then
+        let records = history
+            .records()
+            .unwrap()
+            .into_iter()
+            .map(|record| (record.title, record.timestamp))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            records,
+            vec![
+                ("Game 1".to_string(), day(1)),
+                ("Game 2".to_string(), day(1)),
+                ("Game 1".to_string(), day(3)),
+                ("Game 1".to_string(), day(5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recorded_prices() {
+        testutils::setup_logger();
+        // given
+        let dir = TempDir::new().unwrap();
+        let history = history_in(&dir);
+        let game = |price| Game::with_prices("Game 1", Some(20.0), Some(price));
+        history.record_at(&[game(15.0)], day(1)).unwrap();
+        history.record_at(&[game(10.0)], day(2)).unwrap();
+        history
+            .record_at(&[game(12.0), game(5.0).in_region(Region::De)], day(3))
+            .unwrap();
+        history
+            .record_at(&[Game::with_prices("Game 2", None, None)], day(3))
+            .unwrap();
+
+        // when
+        let recorded_prices = history.recorded_prices().unwrap();
+
+        // then
+        assert_eq!(
+            recorded_prices,
+            HashMap::from([
+                (
+                    ("Game 1".to_string(), None),
+                    RecordedPrices {
+                        lowest: 10.0,
+                        last: 12.0
+                    }
+                ),
+                (
+                    ("Game 1".to_string(), Some(Region::De)),
+                    RecordedPrices {
+                        lowest: 5.0,
+                        last: 5.0
+                    }
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_recording_provider_annotates_recorded_prices() {
+        testutils::setup_logger();
+        // given
+        let dir = TempDir::new().unwrap();
+        let history = history_in(&dir);
+        let game = Game::with_prices("Game 1", Some(20.0), Some(10.0));
+        let recorded = |price| Game::with_prices("Game 1", Some(20.0), Some(price));
+        history.record_at(&[recorded(8.0)], day(1)).unwrap();
+        history.record_at(&[recorded(12.0)], day(2)).unwrap();
+        let provider =
+            RecordingProvider::new(Box::new(StubProvider::with_games(vec![game])), &history);
+
+        // when
+        let fetched = provider
+            .fetch(&WatchedGame::new("Game 1"), Region::En)
+            .unwrap();
+
+        // then
+        assert_eq!(fetched[0].lowest_recorded_price(), Some(8.0));
+        assert!(!fetched[0].is_all_time_low());
+        assert_eq!(fetched[0].price_drop(), Some((12.0, 10.0)));
+    }
+
+    #[test]
+    fn test_recording_provider_stores_only_watched_games() {
+        testutils::setup_logger();
+        // given
+        let dir = TempDir::new().unwrap();
+        let history = history_in(&dir);
+        let games = vec![Game::new("Game 1"), Game::new("Game 2")];
+        let provider = RecordingProvider::new(Box::new(StubProvider::with_games(games)), &history);
+
+        // when
+        let fetched = provider
+            .fetch(&WatchedGame::new("Game 1"), Region::En)
+            .unwrap();
+
+        // then
+        assert_eq!(fetched.len(), 2);
+        let recorded = history
+            .records()
+            .unwrap()
+            .into_iter()
+            .map(|record| record.title)
+            .collect::<Vec<_>>();
+        assert_eq!(recorded, vec!["Game 1".to_string()]);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::configuration::sweetch_dir;
+use crate::data_providers::HttpClient;
+use crate::switch::Game;
+
+use anyhow::Result;
+use log::{debug, warn};
+use std::fs::{self, File};
+use std::io;
+use std::path::PathBuf;
+
+pub(crate) fn icons_dir() -> PathBuf {
+    sweetch_dir().join("icons")
+}
+
+/// Box art of games used as notification icons. Images are downloaded once and kept on disk.
+#[derive(Debug)]
+pub(crate) struct IconCache {
+    dir: PathBuf,
+    client: HttpClient,
+}
+
+impl IconCache {
+    pub(crate) fn new<P: Into<PathBuf>>(dir: P, client: HttpClient) -> Self {
+        Self {
+            dir: dir.into(),
+            client,
+        }
+    }
+
+    /// Path of the box art, `None` if the game has no image or it couldn't be downloaded -
+    /// the notification is sent without the icon then.
+    pub(crate) fn icon(&self, game: &Game) -> Option<PathBuf> {
+        let url = game.image_url()?;
+        match self.download(&url) {
+            Ok(path) => Some(path),
+            Err(e) => {
+                warn!("failed to download box art of '{}': {e:?}", game.title());
+                None
+            }
+        }
+    }
+
+    fn download(&self, url: &str) -> Result<PathBuf> {
+        let path = self.dir.join(file_name(url));
+        if path.exists() {
+            debug!("using cached box art {}", path.display());
+            return Ok(path);
+        }
+        fs::create_dir_all(&self.dir)?;
+        let response = self.client.call(&self.client.get(url))?;
+        // partially downloaded image is never picked up as cached one
+        let tmp_path = path.with_extension("tmp");
+        io::copy(&mut response.into_reader(), &mut File::create(&tmp_path)?)?;
+        fs::rename(tmp_path, &path)?;
+        debug!("downloaded box art from {url} to {}", path.display());
+        Ok(path)
+    }
+}
+
+/// Stable file name for the image, keeping its extension.
+fn file_name(url: &str) -> String {
+    let url = url.split_once("://").map_or(url, |(_, rest)| rest);
+    url.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data_providers::HttpConfig;
+    use crate::testutils::{self, MockResponse, MockServer};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_icon_is_downloaded_once() {
+        testutils::setup_logger();
+        // given
+        let dir = TempDir::new().unwrap();
+        let server = MockServer::start(vec![MockResponse::ok("box art")]);
+        let icons = IconCache::new(dir.path(), HttpClient::new(HttpConfig::without_delays()));
+        let game = Game::new("Game 1").with_image_url(server.url("/images/game-1.jpg"));
+
+        // when
+        let first = icons.icon(&game).unwrap();
+        let second = icons.icon(&game).unwrap();
+
+        // then
+        assert_eq!(first, second);
+        assert_eq!(fs::read_to_string(first).unwrap(), "box art");
+        assert_eq!(server.requests_count(), 1);
+    }
+
+    #[test]
+    fn test_game_without_image_has_no_icon() {
+        testutils::setup_logger();
+        // given
+        let dir = TempDir::new().unwrap();
+        let icons = IconCache::new(dir.path(), HttpClient::new(HttpConfig::without_delays()));
+
+        // when
+        let icon = icons.icon(&Game::new("Game 1"));
+
+        // then
+        assert_eq!(icon, None);
+    }
+
+    #[test]
+    fn test_file_name() {
+        // given
+        let url = "https://fs-prod-cdn.nintendo-europe.com/media/images/SQ_NSwitch_Hades.jpg";
+
+        // when
+        let name = file_name(url);
+
+        // then
+        assert_eq!(
+            name,
+            "fs-prod-cdn.nintendo-europe.com_media_images_SQ_NSwitch_Hades.jpg"
+        );
+    }
+}
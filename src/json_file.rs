@@ -0,0 +1,81 @@
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// State kept in a JSON file next to the configuration, e.g. muted games.
+#[derive(Debug, Clone)]
+pub(crate) struct JsonFile {
+    path: PathBuf,
+}
+
+impl JsonFile {
+    pub(crate) fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Reads the value, a missing file gives the default one.
+    pub(crate) fn load<T: DeserializeOwned + Default>(&self) -> Result<T> {
+        if !self.path.exists() {
+            return Ok(T::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(&self.path)?)?)
+    }
+
+    /// Writes the value to a temporary file which replaces the previous one, so the file is never
+    /// read half-written.
+    pub(crate) fn save<T: Serialize>(&self, value: &T) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp_path = tmp_path(&self.path);
+        fs::write(&tmp_path, serde_json::to_string_pretty(value)?)?;
+        fs::rename(tmp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+pub(crate) fn tmp_path(path: &Path) -> PathBuf {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    PathBuf::from(tmp)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testutils;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_missing_file_gives_default_value() {
+        testutils::setup_logger();
+        // given
+        let dir = TempDir::new().unwrap();
+        let file = JsonFile::new(dir.path().join("state.json"));
+
+        // when
+        let value = file.load::<HashMap<String, u32>>().unwrap();
+
+        // then
+        assert_eq!(value, HashMap::new());
+    }
+
+    #[test]
+    fn test_saved_value_replaces_previous_one() {
+        testutils::setup_logger();
+        // given
+        let dir = TempDir::new().unwrap();
+        let file = JsonFile::new(dir.path().join("nested").join("state.json"));
+        file.save(&vec!["Game 1"]).unwrap();
+
+        // when
+        file.save(&vec!["Game 2"]).unwrap();
+
+        // then
+        assert_eq!(file.load::<Vec<String>>().unwrap(), vec!["Game 2"]);
+        assert!(!tmp_path(&dir.path().join("nested").join("state.json")).exists());
+    }
+}
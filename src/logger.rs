@@ -0,0 +1,79 @@
+use crate::configuration::sweetch_dir;
+
+use anyhow::Result;
+use flexi_logger::{
+    detailed_format, Cleanup, Criterion, Duplicate, FileSpec, Logger, LoggerHandle, Naming,
+};
+use std::path::PathBuf;
+
+const MAX_LOG_FILE_SIZE: u64 = 5 * 1024 * 1024;
+
+const KEPT_LOG_FILES: usize = 3;
+
+/// Logger stays active as long as returned handle is alive.
+pub(crate) fn setup_logger(debug: bool) -> Result<LoggerHandle> {
+    let handle = Logger::try_with_env_or_str(log_level(debug))?
+        .log_to_file(FileSpec::default().directory(logs_dir()))
+        .rotate(
+            Criterion::Size(MAX_LOG_FILE_SIZE),
+            Naming::Numbers,
+            Cleanup::KeepLogFiles(KEPT_LOG_FILES),
+        )
+        .format(detailed_format)
+        .duplicate_to_stderr(Duplicate::Warn)
+        .start()?;
+    Ok(handle)
+}
+
+fn log_level(debug: bool) -> &'static str {
+    if debug {
+        "debug"
+    } else {
+        "info"
+    }
+}
+
+pub(crate) fn logs_dir() -> PathBuf {
+    sweetch_dir().join("logs")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_log_level_with_debug_enabled() {
+        // given
+        let debug = true;
+
+        // when
+        let level = log_level(debug);
+
+        // then
+        assert_eq!(level, "debug");
+    }
+
+    #[test]
+    fn test_log_level_with_debug_disabled() {
+        // given
+        let debug = false;
+
+        // when
+        let level = log_level(debug);
+
+        // then
+        assert_eq!(level, "info");
+    }
+
+    #[test]
+    fn test_logs_dir() {
+        // given
+        let config_dir = dirs::config_dir().unwrap();
+
+        // when
+        let dir = logs_dir();
+
+        // then
+        assert_eq!(dir, config_dir.join("sweetch-bot/logs"));
+    }
+}
@@ -1,33 +1,143 @@
 use crate::configuration::{config_path, Config};
-use crate::notifier::{notify_failure, notify_success};
-use crate::switch::{acceptable_games, fetch};
+use crate::data_providers::{provider, HttpClient};
+use crate::history::{history_path, PriceHistory, RecordingProvider};
+use crate::icons::{icons_dir, IconCache};
+use crate::logger::setup_logger;
+use crate::muted::{muted_games_path, MutedGames};
+use crate::notified::{notified_deals_path, NotifiedDeals};
+use crate::notifier::{notify_fetch_failure, notify_no_games, notify_success, ActionHandlers};
+use crate::scheduler::run_daemon;
+use crate::switch::{acceptable_games, CheckResult};
 
-use anyhow::Result;
-use rutils::file_logger::setup_logger;
+use anyhow::{bail, Result};
+use log::{error, info, warn};
 use std::fs::read_to_string;
+use std::thread;
+use std::time::Duration;
+
+// Failed games are retried only in daemon mode, single run is expected to be retried by its caller.
+const DAEMON_RETRIES: usize = 3;
+
+const RETRY_DELAY: Duration = Duration::from_secs(5 * 60);
 
 mod configuration;
 mod data_providers;
 mod entities;
 mod use_cases;
 
+mod history;
+mod icons;
+mod json_file;
+mod logger;
+mod matching;
+mod muted;
+mod notified;
 mod notifier;
+mod scheduler;
+mod shown;
 mod switch;
 #[cfg(test)]
 mod testutils;
 
 fn main() -> Result<()> {
-    setup_logger()?;
-    check_games_on_sale(&Config::load(&read_to_string(config_path())?)?)?;
+    let cfg = load_config();
+    // when config can't be loaded, logger falls back to defaults, so the failure is logged
+    let _logger = setup_logger(cfg.as_ref().is_ok_and(Config::debug))?;
+    let cfg = cfg.map_err(|e| {
+        error!(
+            "failed to load config from {}: {e:?}",
+            config_path().display()
+        );
+        e
+    })?;
+    match cfg.schedule() {
+        Some(schedule) => {
+            let actions = ActionHandlers::default();
+            run_daemon(schedule, || {
+                check_games_on_sale(&cfg, DAEMON_RETRIES, Some(&actions))
+            })
+        }
+        None => check_games_on_sale(&cfg, 0, None),
+    }
+}
+
+fn load_config() -> Result<Config> {
+    Config::load(&read_to_string(config_path())?)
+}
+
+fn check_games_on_sale(
+    games_cfg: &Config,
+    retries: usize,
+    actions: Option<&ActionHandlers>,
+) -> Result<()> {
+    let history = PriceHistory::new(history_path());
+    let provider =
+        RecordingProvider::new(provider(games_cfg.provider(), games_cfg.http()), &history);
+    let mut result = acceptable_games(&games_cfg.watched_games(), &provider);
+    for attempt in 1..=retries {
+        if !result.has_failures() {
+            break;
+        }
+        info!(
+            "retrying {} failed games in {RETRY_DELAY:?} (attempt {attempt} of {retries})",
+            result.failures().len()
+        );
+        thread::sleep(RETRY_DELAY);
+        let retry = acceptable_games(&result.failed_games(), &provider);
+        result = result.merge_retry(retry);
+    }
+    // history is an optimisation, deals are notified even if it can't be compacted
+    if let Err(e) = history.compact_if_needed() {
+        warn!("failed to compact price history: {e:?}");
+    }
+    let result = result.without_muted(&MutedGames::load(muted_games_path())?);
+    if result.all_failed() {
+        notify_fetch_failure(result.failures().len(), result.checked())?;
+        bail!(
+            "none of {} watched games could be checked",
+            result.checked()
+        );
+    }
+    notify_deals(games_cfg, &result, actions)?;
+    if result.has_failures() {
+        notify_fetch_failure(result.failures().len(), result.checked())?;
+        for failure in result.failures() {
+            error!(
+                "'{}' could not be checked: {:?}",
+                failure.watched_game().title(),
+                failure.error()
+            );
+        }
+        bail!(
+            "{} of {} games could not be checked",
+            result.failures().len(),
+            result.checked()
+        );
+    }
     Ok(())
 }
 
-fn check_games_on_sale(games_cfg: &Config) -> Result<()> {
-    let games = acceptable_games(&games_cfg.watched_games(), fetch);
-    if games.is_empty() {
-        notify_failure()?;
+fn notify_deals(
+    games_cfg: &Config,
+    result: &CheckResult,
+    actions: Option<&ActionHandlers>,
+) -> Result<()> {
+    let mut notified = NotifiedDeals::load(notified_deals_path())?;
+    let new_deals = notified.new_deals(
+        result.games(),
+        &result.failed_games(),
+        games_cfg.notification().renotify_after(),
+    );
+    if !result.has_games() {
+        // with failures the lack of deals is not certain, failure notification is sent instead
+        if !result.has_failures() {
+            notify_no_games(games_cfg.notification().on_empty(), result.checked())?;
+        }
+    } else if new_deals.is_empty() {
+        info!("all games on sale were already notified");
     } else {
-        notify_success(&games)?;
+        let icons = IconCache::new(icons_dir(), HttpClient::new(games_cfg.http().clone()));
+        notify_success(&new_deals, games_cfg.notification(), &icons, actions)?;
     }
-    Ok(())
+    notified.save()
 }
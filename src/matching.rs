@@ -0,0 +1,510 @@
+use crate::entities::{PatternSyntax, TitleMatch, WatchedGame};
+
+use anyhow::{anyhow, Context, Result};
+use log::debug;
+use regex::Regex;
+use std::iter::Peekable;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Checks if the title of a found game is the title of watched game, according to its matching
+/// mode.
+pub(crate) fn title_matches(title: &str, watched_game: &WatchedGame) -> bool {
+    if let Some(pattern) = watched_game.pattern() {
+        return pattern.is_match(title);
+    }
+    let watched_title = watched_game.title();
+    match watched_game.title_match() {
+        TitleMatch::Exact => title == watched_title,
+        TitleMatch::Normalized => normalize(title) == normalize(&watched_title),
+        TitleMatch::Fuzzy => {
+            let similarity = similarity(title, &watched_title);
+            debug!("similarity of [{title}] and [{watched_title}]: {similarity:.2}");
+            similarity >= watched_game.min_similarity()
+        }
+    }
+}
+
+/// Pattern matching titles of many games, e.g. `LEGO*`.
+#[derive(Debug, Clone)]
+pub(crate) struct TitlePattern {
+    source: String,
+    syntax: PatternSyntax,
+    regex: Regex,
+}
+
+impl TitlePattern {
+    pub(crate) fn new(pattern: &str, syntax: PatternSyntax) -> Result<Self> {
+        let regex = match syntax {
+            PatternSyntax::Glob => Regex::new(&glob_to_regex(pattern)),
+            PatternSyntax::Regex => Regex::new(pattern),
+        }
+        .with_context(|| format!("invalid pattern '{pattern}'"))?;
+        Ok(Self {
+            source: pattern.to_string(),
+            syntax,
+            regex,
+        })
+    }
+
+    pub(crate) fn is_match(&self, title: &str) -> bool {
+        let result = self.regex.is_match(title);
+        debug!(
+            "checking [{title}] matches pattern [{}] => {result}",
+            self.source
+        );
+        result
+    }
+
+    /// Longest literal part of the pattern, used to search the store for matching games.
+    pub(crate) fn search_title(&self) -> Result<String> {
+        let literals = match self.syntax {
+            PatternSyntax::Glob => Some(glob_literals(&self.source)),
+            PatternSyntax::Regex => regex_literals(&self.source),
+        };
+        // one search can't find games of all alternatives
+        let literals = literals.ok_or_else(|| {
+            anyhow!(
+                "pattern '{}' has alternatives which can't be searched for at once, \
+                set 'title' as well",
+                self.source
+            )
+        })?;
+        literals
+            .iter()
+            .map(|literal| literal.trim())
+            .max_by_key(|literal| literal.chars().count())
+            .filter(|literal| !literal.is_empty())
+            .map(ToString::to_string)
+            .ok_or_else(|| {
+                anyhow!(
+                    "can't find text to search for in pattern '{}', set 'title' as well",
+                    self.source
+                )
+            })
+    }
+}
+
+/// Texts between wildcards and character classes of the glob.
+fn glob_literals(glob: &str) -> Vec<String> {
+    let mut literals = vec![String::new()];
+    let mut chars = glob.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' | '?' => literals.push(String::new()),
+            '[' if glob_class_closed(&chars) => {
+                chars.by_ref().take_while(|c| *c != ']').for_each(drop);
+                literals.push(String::new());
+            }
+            c => push_literal(&mut literals, c),
+        }
+    }
+    literals
+}
+
+/// Texts which every title matching the regex contains, `None` when the regex has top-level
+/// alternatives. Groups, classes, escape sequences like `\b` and optional characters are skipped.
+fn regex_literals(regex: &str) -> Option<Vec<String>> {
+    let mut literals = vec![String::new()];
+    let mut depth = 0_usize;
+    let mut chars = regex.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped) if !escaped.is_alphanumeric() && depth == 0 => {
+                    push_literal(&mut literals, escaped);
+                }
+                _ => {
+                    // e.g. `\p{Greek}`
+                    if chars.next_if_eq(&'{').is_some() {
+                        chars.by_ref().take_while(|c| *c != '}').for_each(drop);
+                    }
+                    literals.push(String::new());
+                }
+            },
+            '(' => {
+                depth += 1;
+                literals.push(String::new());
+            }
+            ')' => {
+                depth = depth.saturating_sub(1);
+                literals.push(String::new());
+            }
+            '|' if depth == 0 => return None,
+            '[' => {
+                skip_regex_class(&mut chars);
+                literals.push(String::new());
+            }
+            '?' | '*' | '{' => {
+                // quantified character may be missing
+                if depth == 0 {
+                    literals.last_mut().map(String::pop);
+                }
+                if c == '{' {
+                    chars.by_ref().take_while(|c| *c != '}').for_each(drop);
+                }
+                literals.push(String::new());
+            }
+            '+' | '.' | '^' | '$' | '|' => literals.push(String::new()),
+            c if depth == 0 => push_literal(&mut literals, c),
+            _ => {}
+        }
+    }
+    Some(literals)
+}
+
+fn skip_regex_class<I: Iterator<Item = char>>(chars: &mut Peekable<I>) {
+    chars.next_if_eq(&'^');
+    // `]` right at the start is a part of the class
+    chars.next_if_eq(&']');
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            ']' => return,
+            _ => {}
+        }
+    }
+}
+
+fn push_literal(literals: &mut [String], c: char) {
+    if let Some(literal) = literals.last_mut() {
+        literal.push(c);
+    }
+}
+
+impl PartialEq for TitlePattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source && self.syntax == other.syntax
+    }
+}
+
+/// Translates glob to case insensitive regex matching the whole title.
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("(?i)^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '[' if glob_class_closed(&chars) => {
+                regex.push('[');
+                if chars.next_if_eq(&'!').is_some() {
+                    regex.push('^');
+                }
+                for c in chars.by_ref().take_while(|c| *c != ']') {
+                    if c == '\\' || c == '[' {
+                        regex.push('\\');
+                    }
+                    regex.push(c);
+                }
+                regex.push(']');
+            }
+            c => regex.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+fn glob_class_closed<I: Iterator<Item = char> + Clone>(chars: &I) -> bool {
+    chars.clone().any(|c| c == ']')
+}
+
+/// Lowercase title without accents, punctuation and symbols like `™` or `®`, with words separated
+/// by single spaces.
+fn normalize(title: &str) -> String {
+    // symbols go first, compatibility decomposition would turn `™` into `TM`, but accents of
+    // already decomposed titles have to stay next to their letters until they are dropped
+    let is_kept = |c: char| c.is_alphanumeric() || is_combining_mark(c);
+    let stripped = title
+        .chars()
+        .map(|c| if is_kept(c) { c } else { ' ' })
+        .nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .to_lowercase();
+    stripped.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Similarity of normalized titles, from 0 (nothing in common) to 1 (the same).
+fn similarity(a: &str, b: &str) -> f64 {
+    strsim::normalized_levenshtein(&normalize(a), &normalize(b))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testutils;
+
+    #[test]
+    fn test_normalize_removes_trademark_symbols() {
+        testutils::setup_logger();
+        // given
+        let titles = ["DOOM®", "Minecraft™", "Game©"];
+
+        // when
+        let normalized = titles.map(normalize);
+
+        // then
+        assert_eq!(normalized, ["doom", "minecraft", "game"]);
+    }
+
+    #[test]
+    fn test_normalize_removes_punctuation_and_extra_spaces() {
+        testutils::setup_logger();
+        // given
+        let titles = [
+            "Alien: Isolation",
+            "Alien : Isolation",
+            "  Alien -  Isolation ",
+        ];
+
+        // when
+        let normalized = titles.map(normalize);
+
+        // then
+        assert_eq!(normalized, ["alien isolation"; 3]);
+    }
+
+    #[test]
+    fn test_normalize_removes_accents() {
+        testutils::setup_logger();
+        // given
+        let title = "Pokémon Légendes: Arceus";
+
+        // when
+        let normalized = normalize(title);
+
+        // then
+        assert_eq!(normalized, "pokemon legendes arceus");
+    }
+
+    #[test]
+    fn test_normalize_removes_accents_of_decomposed_title() {
+        testutils::setup_logger();
+        // given
+        let title = "Poke\u{301}mon Le\u{301}gendes: Arceus";
+
+        // when
+        let normalized = normalize(title);
+
+        // then
+        assert_eq!(normalized, "pokemon legendes arceus");
+    }
+
+    #[test]
+    fn test_glob_pattern_match() {
+        testutils::setup_logger();
+        // given
+        let watched_game = WatchedGame::with_pattern("lego *", PatternSyntax::Glob);
+
+        // when
+        let matches = [
+            title_matches("LEGO Star Wars: The Skywalker Saga", &watched_game),
+            title_matches("LEGO Worlds", &watched_game),
+            title_matches("The LEGO Movie 2 Videogame", &watched_game),
+        ];
+
+        // then
+        assert_eq!(matches, [true, true, false]);
+    }
+
+    #[test]
+    fn test_glob_pattern_with_character_class() {
+        testutils::setup_logger();
+        // given
+        let watched_game = WatchedGame::with_pattern("Mario Kart [!7]*", PatternSyntax::Glob);
+
+        // when
+        let matches = [
+            title_matches("Mario Kart 8 Deluxe", &watched_game),
+            title_matches("Mario Kart 7", &watched_game),
+        ];
+
+        // then
+        assert_eq!(matches, [true, false]);
+    }
+
+    #[test]
+    fn test_regex_pattern_match() {
+        testutils::setup_logger();
+        // given
+        let watched_game =
+            WatchedGame::with_pattern("^The Legend of Zelda(:| -)", PatternSyntax::Regex);
+
+        // when
+        let matches = [
+            title_matches("The Legend of Zelda: Breath of the Wild", &watched_game),
+            title_matches("The Legend of Zelda - Link's Awakening", &watched_game),
+            title_matches("Hyrule Warriors: The Legend of Zelda", &watched_game),
+        ];
+
+        // then
+        assert_eq!(matches, [true, true, false]);
+    }
+
+    #[test]
+    fn test_invalid_regex_pattern() {
+        testutils::setup_logger();
+        // given
+        let pattern = "Zelda (";
+
+        // when
+        let pattern = TitlePattern::new(pattern, PatternSyntax::Regex);
+
+        // then
+        assert!(pattern.is_err());
+    }
+
+    #[test]
+    fn test_search_title_is_longest_literal_of_pattern() {
+        testutils::setup_logger();
+        // given
+        let patterns = [
+            ("LEGO*", PatternSyntax::Glob),
+            ("^The Legend of Zelda(:| -)", PatternSyntax::Regex),
+            ("Mario Kart [!7]*", PatternSyntax::Glob),
+            (r"\bLEGO\b", PatternSyntax::Regex),
+            (r"^Mario Kart\s\d+ Deluxe$", PatternSyntax::Regex),
+            (r"Pikmin\.? [0-9]", PatternSyntax::Regex),
+        ];
+
+        // when
+        let titles = patterns.map(|(pattern, syntax)| {
+            TitlePattern::new(pattern, syntax)
+                .unwrap()
+                .search_title()
+                .unwrap()
+        });
+
+        // then
+        assert_eq!(
+            titles,
+            [
+                "LEGO",
+                "The Legend of Zelda",
+                "Mario Kart",
+                "LEGO",
+                "Mario Kart",
+                "Pikmin"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_search_title_of_pattern_without_literal() {
+        testutils::setup_logger();
+        // given
+        let pattern = TitlePattern::new(".*", PatternSyntax::Regex).unwrap();
+
+        // when
+        let title = pattern.search_title();
+
+        // then
+        assert!(title.is_err());
+    }
+
+    #[test]
+    fn test_search_title_of_pattern_with_alternatives() {
+        testutils::setup_logger();
+        // given
+        let patterns = ["Zelda|Mario", "^(Zelda|Mario)$"];
+
+        // when
+        let titles = patterns.map(|pattern| {
+            TitlePattern::new(pattern, PatternSyntax::Regex)
+                .unwrap()
+                .search_title()
+                .map_err(|e| e.to_string())
+        });
+
+        // then
+        assert_eq!(
+            titles,
+            [
+                Err(
+                    "pattern 'Zelda|Mario' has alternatives which can't be searched for at once, \
+                    set 'title' as well"
+                        .to_string()
+                ),
+                Err(
+                    "can't find text to search for in pattern '^(Zelda|Mario)$', \
+                    set 'title' as well"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exact_match_requires_equal_titles() {
+        testutils::setup_logger();
+        // given
+        let watched_game = WatchedGame::new("DOOM");
+
+        // when
+        let matches = [
+            title_matches("DOOM", &watched_game),
+            title_matches("DOOM®", &watched_game),
+        ];
+
+        // then
+        assert_eq!(matches, [true, false]);
+    }
+
+    #[test]
+    fn test_normalized_match() {
+        testutils::setup_logger();
+        // given
+        let watched_game =
+            WatchedGame::new("Alien: Isolation").with_title_match(TitleMatch::Normalized);
+
+        // when
+        let matches = [
+            title_matches("ALIEN : ISOLATION™", &watched_game),
+            title_matches("Alien: Isolation 2", &watched_game),
+        ];
+
+        // then
+        assert_eq!(matches, [true, false]);
+    }
+
+    #[test]
+    fn test_fuzzy_match_with_default_similarity() {
+        testutils::setup_logger();
+        // given
+        let watched_game =
+            WatchedGame::new("The Witcher 3: Wild Hunt").with_title_match(TitleMatch::Fuzzy);
+
+        // when
+        let matches = [
+            title_matches("The Witcher® 3: Wild Hunt", &watched_game),
+            title_matches("The Witcher 3 - Wild Hunt!", &watched_game),
+            title_matches("The Witcher 2", &watched_game),
+        ];
+
+        // then
+        assert_eq!(matches, [true, true, false]);
+    }
+
+    #[test]
+    fn test_fuzzy_match_with_custom_similarity() {
+        testutils::setup_logger();
+        // given
+        let watched_game = WatchedGame::new("Hollow Knight")
+            .with_title_match(TitleMatch::Fuzzy)
+            .with_min_similarity(0.5);
+
+        // when
+        let matches = [
+            title_matches("Hollow Knight: Silksong", &watched_game),
+            title_matches("Celeste", &watched_game),
+        ];
+
+        // then
+        assert_eq!(matches, [true, false]);
+    }
+}
@@ -0,0 +1,197 @@
+use crate::configuration::sweetch_dir;
+use crate::entities::Region;
+use crate::json_file::JsonFile;
+use crate::switch::Game;
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+const SNOOZE_DAYS: i64 = 7;
+
+// actions of different notifications are handled in separate threads
+static UPDATING: Mutex<()> = Mutex::new(());
+
+pub(crate) fn muted_games_path() -> PathBuf {
+    sweetch_dir().join("muted_games.json")
+}
+
+/// Game which shouldn't be notified about, set from notification actions.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct MutedGame {
+    title: String,
+    region: Option<Region>,
+    /// End of the snooze, `None` for bought games which are muted for good.
+    until: Option<DateTime<Utc>>,
+}
+
+type GameKey = (String, Option<Region>);
+
+/// Games snoozed or marked as bought by the user.
+#[derive(Debug)]
+pub(crate) struct MutedGames {
+    file: JsonFile,
+    games: HashMap<GameKey, MutedGame>,
+}
+
+impl MutedGames {
+    pub(crate) fn load<P: Into<PathBuf>>(path: P) -> Result<Self> {
+        let file = JsonFile::new(path);
+        let games = file
+            .load::<Vec<MutedGame>>()?
+            .into_iter()
+            .map(|game| ((game.title.clone(), game.region), game))
+            .collect();
+        Ok(Self { file, games })
+    }
+
+    pub(crate) fn save(&self) -> Result<()> {
+        let mut games = self.games.values().collect::<Vec<_>>();
+        games.sort_by(|a, b| a.title.cmp(&b.title));
+        self.file.save(&games)
+    }
+
+    /// Loads, changes and saves muted games, one update at a time, so concurrent updates aren't
+    /// lost.
+    pub(crate) fn update<P, F>(path: P, change: F) -> Result<()>
+    where
+        P: Into<PathBuf>,
+        F: FnOnce(&mut MutedGames),
+    {
+        let _updating = UPDATING.lock().expect("poisoned lock");
+        let mut muted = Self::load(path)?;
+        change(&mut muted);
+        muted.save()
+    }
+
+    /// Mutes games for the next week. Expired snoozes are forgotten.
+    pub(crate) fn snooze(&mut self, games: &[Game]) {
+        self.snooze_at(games, Utc::now());
+    }
+
+    fn snooze_at(&mut self, games: &[Game], now: DateTime<Utc>) {
+        self.games
+            .retain(|_, game| game.until.is_none_or(|until| until > now));
+        self.mute(games, Some(now + Duration::days(SNOOZE_DAYS)));
+    }
+
+    /// Mutes games for good.
+    pub(crate) fn mark_bought(&mut self, games: &[Game]) {
+        self.mute(games, None);
+    }
+
+    fn mute(&mut self, games: &[Game], until: Option<DateTime<Utc>>) {
+        for game in games {
+            info!("muting '{}' until {until:?}", game.title());
+            let muted = MutedGame {
+                title: game.title(),
+                region: game.region(),
+                until,
+            };
+            self.games.insert((game.title(), game.region()), muted);
+        }
+    }
+
+    pub(crate) fn is_muted(&self, game: &Game) -> bool {
+        self.is_muted_at(game, Utc::now())
+    }
+
+    fn is_muted_at(&self, game: &Game, now: DateTime<Utc>) -> bool {
+        let result = self
+            .games
+            .get(&(game.title(), game.region()))
+            .is_some_and(|muted| muted.until.is_none_or(|until| until > now));
+        debug!("checking if '{}' is muted: {result}", game.title());
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testutils::{self, day, load_in_temp_dir};
+    use std::slice;
+    use std::thread;
+    use tempfile::TempDir;
+
+    fn empty_muted_games() -> (TempDir, MutedGames) {
+        load_in_temp_dir("muted_games.json", MutedGames::load)
+    }
+
+    #[test]
+    fn test_snoozed_games_are_muted_for_a_week() {
+        testutils::setup_logger();
+        // given
+        let (_dir, mut muted) = empty_muted_games();
+        let game = Game::new("Game 1");
+
+        // when
+        muted.snooze_at(slice::from_ref(&game), day(1));
+
+        // then
+        assert!(muted.is_muted_at(&game, day(7)));
+        assert!(!muted.is_muted_at(&game, day(8)));
+        assert!(!muted.is_muted_at(&Game::new("Game 2"), day(1)));
+    }
+
+    #[test]
+    fn test_bought_games_are_muted_for_good() {
+        testutils::setup_logger();
+        // given
+        let (_dir, mut muted) = empty_muted_games();
+        let game = Game::new("Game 1").in_region(Region::De);
+
+        // when
+        muted.mark_bought(slice::from_ref(&game));
+
+        // then
+        assert!(muted.is_muted_at(&game, day(31)));
+        assert!(!muted.is_muted_at(&Game::new("Game 1"), day(1)));
+    }
+
+    #[test]
+    fn test_muted_games_are_persisted() {
+        testutils::setup_logger();
+        // given
+        let (dir, mut muted) = empty_muted_games();
+        muted.snooze_at(&[Game::new("Game 1")], day(1));
+        muted.mark_bought(&[Game::new("Game 2")]);
+
+        // when
+        muted.save().unwrap();
+        let loaded = MutedGames::load(dir.path().join("muted_games.json")).unwrap();
+
+        // then
+        assert_eq!(loaded.games, muted.games);
+    }
+
+    #[test]
+    fn test_concurrent_updates_are_not_lost() {
+        testutils::setup_logger();
+        // given
+        let (dir, _muted) = empty_muted_games();
+        let path = dir.path().join("muted_games.json");
+        let games = (1..=8)
+            .map(|idx| Game::new(format!("Game {idx}")))
+            .collect::<Vec<_>>();
+
+        // when
+        thread::scope(|scope| {
+            for game in &games {
+                let path = path.clone();
+                scope.spawn(move || {
+                    MutedGames::update(path, |muted| muted.mark_bought(slice::from_ref(game)))
+                        .unwrap();
+                });
+            }
+        });
+
+        // then
+        let muted = MutedGames::load(path).unwrap();
+        assert!(games.iter().all(|game| muted.is_muted(game)));
+    }
+}
@@ -0,0 +1,303 @@
+use crate::configuration::sweetch_dir;
+use crate::entities::{Region, WatchedGame};
+use crate::json_file::JsonFile;
+use crate::matching::title_matches;
+use crate::switch::{store_ids_match, Game};
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+pub(crate) fn notified_deals_path() -> PathBuf {
+    sweetch_dir().join("notified_deals.json")
+}
+
+/// Deal which was already included in a notification.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct NotifiedDeal {
+    title: String,
+    region: Option<Region>,
+    price: f64,
+    notified_at: DateTime<Utc>,
+    /// Store IDs, so deals of games watched by ID are recognized without the title.
+    #[serde(default)]
+    nsuids: Vec<String>,
+    #[serde(default)]
+    product_codes: Vec<String>,
+    /// End of the notified sale, a different one means the sale was restarted in between checks.
+    #[serde(default)]
+    sale_end: Option<DateTime<Utc>>,
+}
+
+impl NotifiedDeal {
+    fn is_deal_of(&self, watched_game: &WatchedGame) -> bool {
+        if watched_game.has_store_id() {
+            store_ids_match(&self.nsuids, &self.product_codes, watched_game)
+        } else {
+            title_matches(&self.title, watched_game)
+        }
+    }
+}
+
+type DealKey = (String, Option<Region>);
+
+/// Remembers notified deals, so the same deal is not announced on every run.
+#[derive(Debug)]
+pub(crate) struct NotifiedDeals {
+    file: JsonFile,
+    deals: HashMap<DealKey, NotifiedDeal>,
+}
+
+impl NotifiedDeals {
+    pub(crate) fn load<P: Into<PathBuf>>(path: P) -> Result<Self> {
+        let file = JsonFile::new(path);
+        let deals = file
+            .load::<Vec<NotifiedDeal>>()?
+            .into_iter()
+            .map(|deal| ((deal.title.clone(), deal.region), deal))
+            .collect();
+        Ok(Self { file, deals })
+    }
+
+    pub(crate) fn save(&self) -> Result<()> {
+        let mut deals = self.deals.values().collect::<Vec<_>>();
+        deals.sort_by(|a, b| a.title.cmp(&b.title));
+        self.file.save(&deals)
+    }
+
+    /// Returns deals worth notifying about: new ones, those with lower price than previously and
+    /// those notified longer than `renotify_after` ago. Deals which are gone are forgotten, so
+    /// a restarted sale is treated as a new one, as is a sale with a different end date. Deals of
+    /// `unchecked` games are kept as they are, a failed fetch doesn't mean the sale has ended.
+    pub(crate) fn new_deals(
+        &mut self,
+        games: Vec<Game>,
+        unchecked: &[WatchedGame],
+        renotify_after: Option<Duration>,
+    ) -> Vec<Game> {
+        self.new_deals_at(games, unchecked, renotify_after, Utc::now())
+    }
+
+    fn new_deals_at(
+        &mut self,
+        games: Vec<Game>,
+        unchecked: &[WatchedGame],
+        renotify_after: Option<Duration>,
+        now: DateTime<Utc>,
+    ) -> Vec<Game> {
+        let mut current = HashMap::new();
+        let mut new_deals = Vec::new();
+        for game in games {
+            let key = (game.title(), game.region());
+            let price = game.lowest_price();
+            let notified_at = match self.deals.get(&key) {
+                Some(deal) if !should_renotify(deal, &game, renotify_after, now) => {
+                    debug!("already notified about '{}'", deal.title);
+                    deal.notified_at
+                }
+                _ => {
+                    new_deals.push(game.clone());
+                    now
+                }
+            };
+            let deal = NotifiedDeal {
+                title: game.title(),
+                region: game.region(),
+                price,
+                notified_at,
+                nsuids: game.nsuids().to_vec(),
+                product_codes: game.product_codes().to_vec(),
+                sale_end: game.sale_end(),
+            };
+            current.insert(key, deal);
+        }
+        info!("{} of {} deals are new", new_deals.len(), current.len());
+        for (key, deal) in self.deals.drain() {
+            let unchecked = unchecked.iter().any(|game| deal.is_deal_of(game));
+            if unchecked {
+                current.entry(key).or_insert(deal);
+            }
+        }
+        self.deals = current;
+        new_deals
+    }
+}
+
+fn should_renotify(
+    deal: &NotifiedDeal,
+    game: &Game,
+    renotify_after: Option<Duration>,
+    now: DateTime<Utc>,
+) -> bool {
+    let price_dropped = game.lowest_price() < deal.price;
+    // unknown end dates, e.g. of deals notified by older versions, don't restart the sale
+    let sale_restarted = matches!(
+        (deal.sale_end, game.sale_end()),
+        (Some(notified_end), Some(end)) if notified_end != end
+    );
+    let interval_passed = renotify_after.is_some_and(|interval| now - deal.notified_at >= interval);
+    price_dropped || sale_restarted || interval_passed
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testutils::{self, day, load_in_temp_dir};
+    use tempfile::TempDir;
+
+    fn deal(title: &str, price: f64) -> Game {
+        Game::with_prices(title, Some(20.0), Some(price))
+    }
+
+    fn empty_deals() -> (TempDir, NotifiedDeals) {
+        load_in_temp_dir("notified_deals.json", NotifiedDeals::load)
+    }
+
+    #[test]
+    fn test_new_deals_are_notified() {
+        testutils::setup_logger();
+        // given
+        let (_dir, mut notified) = empty_deals();
+        let games = vec![deal("Game 1", 10.0), deal("Game 2", 10.0)];
+
+        // when
+        let new_deals = notified.new_deals_at(games.clone(), &[], None, day(1));
+
+        // then
+        assert_eq!(new_deals, games);
+    }
+
+    #[test]
+    fn test_already_notified_deals_are_skipped() {
+        testutils::setup_logger();
+        // given
+        let (_dir, mut notified) = empty_deals();
+        notified.new_deals_at(vec![deal("Game 1", 10.0)], &[], None, day(1));
+
+        // when
+        let new_deals = notified.new_deals_at(
+            vec![deal("Game 1", 10.0), deal("Game 2", 10.0)],
+            &[],
+            None,
+            day(2),
+        );
+
+        // then
+        assert_eq!(new_deals, vec![deal("Game 2", 10.0)]);
+    }
+
+    #[test]
+    fn test_deals_with_dropped_price_are_notified() {
+        testutils::setup_logger();
+        // given
+        let (_dir, mut notified) = empty_deals();
+        notified.new_deals_at(vec![deal("Game 1", 10.0)], &[], None, day(1));
+
+        // when
+        let new_deals = notified.new_deals_at(vec![deal("Game 1", 8.0)], &[], None, day(2));
+
+        // then
+        assert_eq!(new_deals, vec![deal("Game 1", 8.0)]);
+    }
+
+    #[test]
+    fn test_restarted_deals_are_notified() {
+        testutils::setup_logger();
+        // given
+        let (_dir, mut notified) = empty_deals();
+        notified.new_deals_at(vec![deal("Game 1", 10.0)], &[], None, day(1));
+        notified.new_deals_at(vec![], &[], None, day(2));
+
+        // when
+        let new_deals = notified.new_deals_at(vec![deal("Game 1", 10.0)], &[], None, day(3));
+
+        // then
+        assert_eq!(new_deals, vec![deal("Game 1", 10.0)]);
+    }
+
+    #[test]
+    fn test_deals_with_new_sale_end_are_notified() {
+        testutils::setup_logger();
+        // given
+        let (_dir, mut notified) = empty_deals();
+        let first_sale = deal("Game 1", 10.0).with_sale_end(Some(day(5)));
+        let second_sale = deal("Game 1", 10.0).with_sale_end(Some(day(20)));
+        notified.new_deals_at(vec![first_sale.clone()], &[], None, day(1));
+
+        // when
+        let same_sale = notified.new_deals_at(vec![first_sale], &[], None, day(2));
+        let restarted_sale = notified.new_deals_at(vec![second_sale.clone()], &[], None, day(9));
+
+        // then
+        assert!(same_sale.is_empty());
+        assert_eq!(restarted_sale, vec![second_sale]);
+    }
+
+    #[test]
+    fn test_deals_of_unchecked_games_are_kept() {
+        testutils::setup_logger();
+        // given
+        let (_dir, mut notified) = empty_deals();
+        notified.new_deals_at(vec![deal("Game 1", 10.0)], &[], None, day(1));
+        notified.new_deals_at(vec![], &[WatchedGame::new("Game 1")], None, day(2));
+
+        // when
+        let new_deals = notified.new_deals_at(vec![deal("Game 1", 10.0)], &[], None, day(3));
+
+        // then
+        assert_eq!(new_deals, vec![]);
+    }
+
+    #[test]
+    fn test_deals_of_unchecked_games_watched_by_store_id_are_kept() {
+        testutils::setup_logger();
+        // given
+        let (_dir, mut notified) = empty_deals();
+        let deal = deal("Game 1", 10.0).with_nsuid("70010000025453");
+        let watched_game = WatchedGame::new("70010000025453").with_nsuid("70010000025453");
+        notified.new_deals_at(vec![deal.clone()], &[], None, day(1));
+        notified.new_deals_at(vec![], &[watched_game], None, day(2));
+
+        // when
+        let new_deals = notified.new_deals_at(vec![deal], &[], None, day(3));
+
+        // then
+        assert_eq!(new_deals, vec![]);
+    }
+
+    #[test]
+    fn test_deals_are_renotified_after_interval() {
+        testutils::setup_logger();
+        // given
+        let (_dir, mut notified) = empty_deals();
+        let interval = Some(Duration::days(2));
+        notified.new_deals_at(vec![deal("Game 1", 10.0)], &[], interval, day(1));
+        let not_yet = notified.new_deals_at(vec![deal("Game 1", 10.0)], &[], interval, day(2));
+
+        // when
+        let new_deals = notified.new_deals_at(vec![deal("Game 1", 10.0)], &[], interval, day(3));
+
+        // then
+        assert_eq!(not_yet, vec![]);
+        assert_eq!(new_deals, vec![deal("Game 1", 10.0)]);
+    }
+
+    #[test]
+    fn test_notified_deals_survive_save_and_load() {
+        testutils::setup_logger();
+        // given
+        let (dir, mut notified) = empty_deals();
+        notified.new_deals_at(vec![deal("Game 1", 10.0)], &[], None, day(1));
+        notified.save().unwrap();
+
+        // when
+        let mut loaded = NotifiedDeals::load(dir.path().join("notified_deals.json")).unwrap();
+        let new_deals = loaded.new_deals_at(vec![deal("Game 1", 10.0)], &[], None, day(2));
+
+        // then
+        assert_eq!(new_deals, vec![]);
+    }
+}
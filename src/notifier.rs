@@ -1,40 +1,331 @@
+use crate::configuration::{NotificationConfig, NotificationMode, OnEmpty, Template};
+use crate::icons::IconCache;
+use crate::muted::{muted_games_path, MutedGames};
+use crate::shown::{shown_notifications_path, ShownNotifications};
 use crate::switch::Game;
 
 use anyhow::Result;
-use log::info;
+use chrono::Local;
+use log::{debug, error, info};
+use notify_rust::{Notification, NotificationHandle, Timeout};
 use rutils::desktop_notifier::notify;
 use std::cmp;
+use std::collections::HashMap;
 use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::slice;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
 
-const MAX_GAMES_IN_NOTIFICATION: usize = 10;
+const DEFAULT_TITLE: &str = "Sweetch Bot";
 
-pub(crate) fn notify_success(games: &[Game]) -> Result<()> {
+const COMBINED_KEY: &str = "combined";
+
+// notifications with actions expire, so servers honoring it release the threads waiting for them
+const ACTIONS_EXPIRE_AFTER_MS: u32 = 12 * 60 * 60 * 1000;
+
+const OPEN_ACTION: &str = "open";
+
+const SNOOZE_ACTION: &str = "snooze";
+
+const BOUGHT_ACTION: &str = "bought";
+
+/// Threads waiting for actions of notifications shown by the daemon, at most one for each
+/// [`replacement_key`]. Single run exits right after notifying, so it doesn't offer actions.
+#[derive(Debug, Default)]
+pub(crate) struct ActionHandlers {
+    /// Games of the latest notification with given key. A replaced notification keeps its id, so
+    /// the thread waiting for it handles actions of the replacement.
+    games: Arc<Mutex<HashMap<String, Vec<Game>>>>,
+    threads: Mutex<HashMap<String, JoinHandle<()>>>,
+}
+
+impl ActionHandlers {
+    fn wait_for_action(&self, key: String, games: &[Game], handle: NotificationHandle) {
+        self.games
+            .lock()
+            .expect("poisoned lock")
+            .insert(key.clone(), games.to_vec());
+        let mut threads = self.threads.lock().expect("poisoned lock");
+        threads.retain(|_, thread| !thread.is_finished());
+        if threads.contains_key(&key) {
+            debug!("already waiting for actions of '{key}'");
+            return;
+        }
+        let latest_games = Arc::clone(&self.games);
+        let thread_key = key.clone();
+        let thread = thread::spawn(move || {
+            handle.wait_for_action(|action| {
+                on_action(action, &latest_games, &thread_key, &muted_games_path());
+            });
+        });
+        threads.insert(key, thread);
+    }
+}
+
+fn on_action(
+    action: &str,
+    latest_games: &Mutex<HashMap<String, Vec<Game>>>,
+    key: &str,
+    muted_path: &Path,
+) {
+    let games = latest_games
+        .lock()
+        .expect("poisoned lock")
+        .remove(key)
+        .unwrap_or_default();
+    if let Err(e) = handle_action(action, &games, muted_path) {
+        error!("failed to handle '{action}' notification action: {e:?}");
+    }
+}
+
+pub(crate) fn notify_success(
+    games: &[Game],
+    config: &NotificationConfig,
+    icons: &IconCache,
+    actions: Option<&ActionHandlers>,
+) -> Result<()> {
     info!("found games on sale - sending notification");
-    notify(build_body(games)?)?;
+    let mut shown = ShownNotifications::load(shown_notifications_path())?;
+    match config.mode() {
+        NotificationMode::Combined => {
+            let title = config.title().map_or_else(
+                || DEFAULT_TITLE.to_string(),
+                |title| build_title(games, title),
+            );
+            let body = build_body(games, config)?;
+            show(&title, &body, None, games, &mut shown, actions)?;
+        }
+        NotificationMode::PerGame => {
+            for game in games {
+                let games = slice::from_ref(game);
+                let body = build_body(games, config)?;
+                let title = game_title(game, config);
+                show(
+                    &title,
+                    body.trim_end(),
+                    icons.icon(game),
+                    games,
+                    &mut shown,
+                    actions,
+                )?;
+            }
+        }
+    }
+    shown.save()
+}
+
+/// Shows notification which replaces the previous one about the same games, so re-runs of the
+/// daemon don't stack popups. Actions are offered only when there's someone to handle them.
+fn show(
+    title: &str,
+    body: &str,
+    icon: Option<PathBuf>,
+    games: &[Game],
+    shown: &mut ShownNotifications,
+    handlers: Option<&ActionHandlers>,
+) -> Result<()> {
+    let mut notification = Notification::new();
+    notification.summary(title).body(body);
+    if let Some(icon) = icon {
+        notification.icon(&icon.to_string_lossy());
+    }
+    if handlers.is_some() {
+        for (action, label) in actions(games) {
+            notification.action(action, label);
+        }
+        notification.timeout(Timeout::Milliseconds(ACTIONS_EXPIRE_AFTER_MS));
+    }
+    let key = replacement_key(games);
+    if let Some(id) = shown.id(&key) {
+        notification.id(id);
+    }
+    let handle = notification.show()?;
+    shown.insert(key.clone(), handle.id());
+    if let Some(handlers) = handlers {
+        handlers.wait_for_action(key, games, handle);
+    }
     Ok(())
 }
 
-fn build_body(games: &[Game]) -> Result<String> {
-    let max_len = cmp::min(MAX_GAMES_IN_NOTIFICATION, games.len());
+/// Notifications about the same game replace each other, so do combined ones.
+fn replacement_key(games: &[Game]) -> String {
+    match games {
+        [game] => match game.region() {
+            Some(region) => format!("{} [{region}]", game.title()),
+            None => game.title(),
+        },
+        _ => COMBINED_KEY.to_string(),
+    }
+}
+
+/// Opening the store page and buying make sense only for a single game, snoozing for all of them.
+fn actions(games: &[Game]) -> Vec<(&'static str, &'static str)> {
+    match games {
+        [game] => {
+            let mut actions = Vec::new();
+            if game.url().is_some() {
+                actions.push((OPEN_ACTION, "Open store page"));
+            }
+            actions.push((SNOOZE_ACTION, "Snooze for 7 days"));
+            actions.push((BOUGHT_ACTION, "I bought it \u{2014} stop watching"));
+            actions
+        }
+        _ => vec![(SNOOZE_ACTION, "Snooze these games for 7 days")],
+    }
+}
+
+fn handle_action(action: &str, games: &[Game], muted_path: &Path) -> Result<()> {
+    match action {
+        OPEN_ACTION => {
+            for url in games.iter().filter_map(Game::url) {
+                info!("opening {url}");
+                Command::new("xdg-open").arg(url).spawn()?;
+            }
+        }
+        SNOOZE_ACTION => MutedGames::update(muted_path, |muted| muted.snooze(games))?,
+        BOUGHT_ACTION => MutedGames::update(muted_path, |muted| muted.mark_bought(games))?,
+        _ => debug!("notification closed with '{action}'"),
+    }
+    Ok(())
+}
+
+/// Title of the notification about a single game, rendered like its item.
+fn game_title(game: &Game, config: &NotificationConfig) -> String {
+    config
+        .title()
+        .map_or_else(|| game.title(), |title| render_item(title, game))
+}
+
+fn build_title(games: &[Game], template: &Template) -> String {
+    template.render(|field| match field {
+        "count" => games.len().to_string(),
+        _ => String::new(),
+    })
+}
+
+fn build_body(games: &[Game], config: &NotificationConfig) -> Result<String> {
+    let max_items = config.max_items();
+    let max_len = cmp::min(max_items, games.len());
     let mut body = String::new();
     for game in games.iter().take(max_len) {
-        writeln!(body, "- {}", game.title())?;
+        match config.item() {
+            Some(item) => body.push_str(&render_item(item, game)),
+            None => write_item(&mut body, game)?,
+        }
+        writeln!(body)?;
     }
-    if games.len() > MAX_GAMES_IN_NOTIFICATION {
-        write!(body, "and {} more", games.len() - MAX_GAMES_IN_NOTIFICATION)?;
+    if games.len() > max_items {
+        write!(body, "and {} more", games.len() - max_items)?;
     }
     Ok(body)
 }
 
-pub(crate) fn notify_failure() -> Result<()> {
-    info!("no games on sale found - sending notification");
-    notify("No games on sale found.")?;
+fn write_item(body: &mut String, game: &Game) -> Result<()> {
+    write!(body, "- {}", game.title())?;
+    if let Some(region) = game.region() {
+        write!(body, " [{region}]")?;
+    }
+    if let Some(prices) = prices(game) {
+        write!(body, ": {prices}")?;
+    }
+    if let Some(sale_end) = sale_end(game) {
+        write!(body, " until {sale_end}")?;
+    }
+    if let Some((previous, current)) = game.price_drop() {
+        write!(body, " - price dropped from {previous:.2} to {current:.2}")?;
+    }
+    if game.is_new_all_time_low() {
+        write!(body, " - new all-time low")?;
+    }
+    Ok(())
+}
+
+/// Fills the item template, unknown values are left empty.
+fn render_item(template: &Template, game: &Game) -> String {
+    let amount = |price: Option<f64>| price.map(|price| format!("{price:.2}"));
+    template.render(|field| {
+        let value = match field {
+            "title" => Some(game.title()),
+            "region" => game.region().map(|region| region.to_string()),
+            "price" => amount(current_price(game)),
+            "regular_price" => amount(game.price_regular()),
+            "discount" => game
+                .discount_percent()
+                .map(|discount| format!("{discount:.0}")),
+            "currency" => game.region().map(|region| region.currency().to_string()),
+            "sale_end" => sale_end(game),
+            "previous_price" => amount(game.price_drop().map(|(previous, _)| previous)),
+            "all_time_low" => game
+                .is_new_all_time_low()
+                .then(|| "new all-time low".to_string()),
+            _ => None,
+        };
+        value.unwrap_or_default()
+    })
+}
+
+/// Last day of the sale in local time.
+fn sale_end(game: &Game) -> Option<String> {
+    game.sale_end()
+        .map(|end| end.with_timezone(&Local).format("%Y-%m-%d").to_string())
+}
+
+fn current_price(game: &Game) -> Option<f64> {
+    game.price_discounted().or(game.price_regular())
+}
+
+/// Current price with the currency of the region, followed by the regular price and the discount
+/// when the game is discounted.
+fn prices(game: &Game) -> Option<String> {
+    let currency = game
+        .region()
+        .map(|region| format!(" {}", region.currency()))
+        .unwrap_or_default();
+    let price = current_price(game)?;
+    let discount = match (game.price_regular(), game.discount_percent()) {
+        (Some(regular), Some(discount)) => {
+            format!(" instead of {regular:.2}{currency} (-{discount:.0}%)")
+        }
+        _ => String::new(),
+    };
+    Some(format!("{price:.2}{currency}{discount}"))
+}
+
+pub(crate) fn notify_no_games(on_empty: OnEmpty, checked_games: usize) -> Result<()> {
+    match on_empty {
+        OnEmpty::Notify => {
+            info!("no games on sale found - sending notification");
+            notify("No games on sale found.")?;
+        }
+        OnEmpty::Log => info!("checked {checked_games} watched games - no games on sale found"),
+        OnEmpty::Silent => debug!("no games on sale found"),
+    }
     Ok(())
 }
 
+pub(crate) fn notify_fetch_failure(failed: usize, checked: usize) -> Result<()> {
+    info!("failed to check {failed} of {checked} games - sending notification");
+    notify(fetch_failure_body(failed, checked))?;
+    Ok(())
+}
+
+fn fetch_failure_body(failed: usize, checked: usize) -> String {
+    if failed == checked {
+        format!("Failed to check games on sale, none of {checked} games could be checked.")
+    } else {
+        format!("{failed} of {checked} games could not be checked.")
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::entities::Region;
+    use crate::testutils;
+    use chrono::{TimeZone, Utc};
+    use tempfile::TempDir;
 
     #[test]
     fn test_build_body_with_few_games() {
@@ -42,20 +333,326 @@ mod test {
         let games = vec![Game::new("Game 1"), Game::new("Game 2")];
 
         // when
-        let body = build_body(&games).unwrap();
+        let body = build_body(&games, &NotificationConfig::default()).unwrap();
 
         // then
         assert_eq!(body, "- Game 1\n- Game 2\n");
     }
 
+    #[test]
+    fn test_build_body_with_regions() {
+        // given
+        let games = vec![
+            Game::new("Game 1").in_region(Region::De),
+            Game::new("Game 2"),
+        ];
+
+        // when
+        let body = build_body(&games, &NotificationConfig::default()).unwrap();
+
+        // then
+        assert_eq!(body, "- Game 1 [de]\n- Game 2\n");
+    }
+
+    #[test]
+    fn test_build_body_with_new_all_time_low() {
+        // given
+        let games = vec![
+            Game::with_prices("Game 1", Some(20.0), Some(10.0))
+                .with_lowest_recorded_price(Some(12.0)),
+            Game::with_prices("Game 2", Some(20.0), Some(10.0))
+                .with_lowest_recorded_price(Some(10.0)),
+        ];
+
+        // when
+        let body = build_body(&games, &NotificationConfig::default()).unwrap();
+
+        // then
+        assert_eq!(
+            body,
+            "- Game 1: 10.00 instead of 20.00 (-50%) - new all-time low\n\
+            - Game 2: 10.00 instead of 20.00 (-50%)\n"
+        );
+    }
+
+    #[test]
+    fn test_build_body_with_price_drop() {
+        // given
+        let games = vec![
+            Game::with_prices("Game 1", Some(19.99), None).with_last_recorded_price(Some(24.99)),
+            Game::with_prices("Game 2", Some(20.0), None).with_last_recorded_price(Some(20.0)),
+        ];
+
+        // when
+        let body = build_body(&games, &NotificationConfig::default()).unwrap();
+
+        // then
+        assert_eq!(
+            body,
+            "- Game 1: 19.99 - price dropped from 24.99 to 19.99\n- Game 2: 20.00\n"
+        );
+    }
+
+    #[test]
+    fn test_build_body_with_sale_end() {
+        // given
+        // the date is shown in local time, whatever the timezone of the machine
+        let end = Local.with_ymd_and_hms(2022, 12, 15, 23, 59, 59).unwrap();
+        let games = vec![
+            Game::with_prices("Game 1", Some(20.0), Some(10.0))
+                .with_sale_end(Some(end.with_timezone(&Utc))),
+            Game::with_prices("Game 2", Some(20.0), Some(10.0)),
+        ];
+
+        // when
+        let body = build_body(&games, &NotificationConfig::default()).unwrap();
+
+        // then
+        assert_eq!(
+            body,
+            "- Game 1: 10.00 instead of 20.00 (-50%) until 2022-12-15\n\
+            - Game 2: 10.00 instead of 20.00 (-50%)\n"
+        );
+    }
+
+    #[test]
+    fn test_build_body_with_prices() {
+        // given
+        let games = vec![
+            Game::with_prices("Game 1", Some(19.99), Some(9.99)).in_region(Region::De),
+            Game::with_prices("Game 2", Some(20.0), None).in_region(Region::Us),
+            Game::with_prices("Game 3", Some(20.0), Some(15.0)),
+        ];
+
+        // when
+        let body = build_body(&games, &NotificationConfig::default()).unwrap();
+
+        // then
+        assert_eq!(
+            body,
+            "- Game 1 [de]: 9.99 EUR instead of 19.99 EUR (-50%)\n\
+            - Game 2 [us]: 20.00 USD\n\
+            - Game 3: 15.00 instead of 20.00 (-25%)\n"
+        );
+    }
+
+    #[test]
+    fn test_build_body_with_item_template() {
+        // given
+        let games = vec![
+            Game::with_prices("Game 1", Some(19.99), Some(9.99)).in_region(Region::De),
+            Game::with_prices("Game 2", Some(20.0), None),
+            Game::new("Game 3"),
+        ];
+        let config = NotificationConfig::default()
+            .with_item("{{title}} ({{region}}): {{price}} {{currency}}, -{{discount}}%")
+            .with_max_items(2);
+
+        // when
+        let body = build_body(&games, &config).unwrap();
+
+        // then
+        assert_eq!(
+            body,
+            "Game 1 (de): 9.99 EUR, -50%\nGame 2 (): 20.00 , -%\nand 1 more"
+        );
+    }
+
+    #[test]
+    fn test_build_body_with_item_template_marking_price_changes() {
+        // given
+        let games = vec![
+            Game::with_prices("Game 1", Some(19.99), None).with_last_recorded_price(Some(24.99)),
+            Game::with_prices("Game 2", Some(20.0), Some(10.0))
+                .with_lowest_recorded_price(Some(12.0)),
+        ];
+        let config = NotificationConfig::default()
+            .with_item("{{title}}: {{price}} (was {{previous_price}}) {{all_time_low}}");
+
+        // when
+        let body = build_body(&games, &config).unwrap();
+
+        // then
+        assert_eq!(
+            body,
+            "Game 1: 19.99 (was 24.99) \nGame 2: 10.00 (was ) new all-time low\n"
+        );
+    }
+
+    #[test]
+    fn test_build_title() {
+        // given
+        let games = vec![Game::new("Game 1"), Game::new("Game 2")];
+        let config = NotificationConfig::default().with_title("{{count}} games on sale");
+
+        // when
+        let title = build_title(&games, config.title().unwrap());
+
+        // then
+        assert_eq!(title, "2 games on sale");
+    }
+
+    #[test]
+    fn test_game_title() {
+        // given
+        let game = Game::with_prices("Game 1", Some(20.0), Some(10.0)).in_region(Region::De);
+        let config = NotificationConfig::default()
+            .with_mode(NotificationMode::PerGame)
+            .with_title("{{title}} for {{price}} {{currency}}");
+
+        // when
+        let titles = [
+            game_title(&game, &config),
+            game_title(&game, &NotificationConfig::default()),
+        ];
+
+        // then
+        assert_eq!(
+            titles,
+            ["Game 1 for 10.00 EUR".to_string(), "Game 1".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_actions_for_single_game() {
+        // given
+        let games = vec![Game::new("Game 1").with_url("https://www.nintendo.de/game-1")];
+
+        // when
+        let actions = actions(&games);
+
+        // then
+        assert_eq!(
+            actions,
+            vec![
+                (OPEN_ACTION, "Open store page"),
+                (SNOOZE_ACTION, "Snooze for 7 days"),
+                (BOUGHT_ACTION, "I bought it \u{2014} stop watching"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_actions_for_many_games() {
+        // given
+        let games = vec![Game::new("Game 1"), Game::new("Game 2")];
+
+        // when
+        let actions = actions(&games);
+
+        // then
+        assert_eq!(
+            actions,
+            vec![(SNOOZE_ACTION, "Snooze these games for 7 days")]
+        );
+    }
+
+    #[test]
+    fn test_action_applies_to_games_of_latest_notification() {
+        testutils::setup_logger();
+        // given
+        let dir = TempDir::new().unwrap();
+        let muted_path = dir.path().join("muted_games.json");
+        let latest_games = Mutex::new(HashMap::from([(
+            COMBINED_KEY.to_string(),
+            vec![Game::new("Game 2"), Game::new("Game 3")],
+        )]));
+
+        // when
+        on_action(SNOOZE_ACTION, &latest_games, COMBINED_KEY, &muted_path);
+
+        // then
+        let muted = MutedGames::load(muted_path).unwrap();
+        assert!(!muted.is_muted(&Game::new("Game 1")));
+        assert!(muted.is_muted(&Game::new("Game 2")));
+        assert!(muted.is_muted(&Game::new("Game 3")));
+        assert!(latest_games.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_bought_action_mutes_game() {
+        testutils::setup_logger();
+        // given
+        let dir = TempDir::new().unwrap();
+        let muted_path = dir.path().join("muted_games.json");
+        let game = Game::new("Game 1").in_region(Region::De);
+        let key = replacement_key(slice::from_ref(&game));
+        let latest_games = Mutex::new(HashMap::from([(key.clone(), vec![game.clone()])]));
+
+        // when
+        on_action(BOUGHT_ACTION, &latest_games, &key, &muted_path);
+
+        // then
+        assert!(MutedGames::load(muted_path).unwrap().is_muted(&game));
+    }
+
+    #[test]
+    fn test_closed_notification_mutes_nothing() {
+        testutils::setup_logger();
+        // given
+        let dir = TempDir::new().unwrap();
+        let muted_path = dir.path().join("muted_games.json");
+        let latest_games = Mutex::new(HashMap::from([(
+            "Game 1".to_string(),
+            vec![Game::new("Game 1")],
+        )]));
+
+        // when
+        on_action("__closed", &latest_games, "Game 1", &muted_path);
+
+        // then
+        assert!(!muted_path.exists());
+    }
+
+    #[test]
+    fn test_replacement_key() {
+        // given
+        let single = [Game::new("Game 1").in_region(Region::De)];
+        let many = [Game::new("Game 1"), Game::new("Game 2")];
+
+        // when
+        let keys = [replacement_key(&single), replacement_key(&many)];
+
+        // then
+        assert_eq!(keys, ["Game 1 [de]".to_string(), COMBINED_KEY.to_string()]);
+    }
+
+    #[test]
+    fn test_fetch_failure_body_with_partial_failure() {
+        // given
+        let (failed, checked) = (3, 12);
+
+        // when
+        let body = fetch_failure_body(failed, checked);
+
+        // then
+        assert_eq!(body, "3 of 12 games could not be checked.");
+    }
+
+    #[test]
+    fn test_fetch_failure_body_with_total_failure() {
+        // given
+        let (failed, checked) = (12, 12);
+
+        // when
+        let body = fetch_failure_body(failed, checked);
+
+        // then
+        assert_eq!(
+            body,
+            "Failed to check games on sale, none of 12 games could be checked."
+        );
+    }
+
     #[test]
     fn test_build_body_with_too_many_games() {
         // given
-        let games = vec![Game::new("Game Title"); MAX_GAMES_IN_NOTIFICATION + 1];
-        let expected = format!("{}and 1 more", "- Game Title\n".repeat(10));
+        let max_items = NotificationConfig::default().max_items();
+        let games = vec![Game::new("Game Title"); max_items + 1];
+        let expected = format!("{}and 1 more", "- Game Title\n".repeat(max_items));
 
         // when
-        let body = build_body(&games).unwrap();
+        let body = build_body(&games, &NotificationConfig::default()).unwrap();
 
         // then
         assert_eq!(body, expected);
@@ -0,0 +1,201 @@
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Duration, Local, NaiveTime, TimeZone};
+use log::{debug, error, info};
+use serde::Deserialize;
+use std::thread;
+
+// Sleeping in chunks lets the daemon notice wall clock jumps, e.g. after the laptop resumes
+// from suspend, instead of oversleeping the scheduled slot.
+const MAX_SLEEP_CHUNK_SECS: i64 = 60;
+
+const TIME_FORMATS: [&str; 3] = ["%I:%M %p", "%I:%M%p", "%H:%M"];
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(try_from = "RawSchedule")]
+pub(crate) struct Schedule {
+    run_at: Vec<NaiveTime>,
+}
+
+#[derive(Deserialize)]
+struct RawSchedule {
+    run_at: Vec<String>,
+}
+
+impl TryFrom<RawSchedule> for Schedule {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: RawSchedule) -> Result<Self> {
+        if raw.run_at.is_empty() {
+            bail!("schedule needs at least one time in 'run_at'");
+        }
+        let mut run_at = raw
+            .run_at
+            .iter()
+            .map(|time| parse_time(time))
+            .collect::<Result<Vec<_>>>()?;
+        run_at.sort();
+        run_at.dedup();
+        Ok(Self { run_at })
+    }
+}
+
+fn parse_time(time: &str) -> Result<NaiveTime> {
+    let time = time.trim();
+    TIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(time, format).ok())
+        .ok_or_else(|| anyhow!("invalid time '{time}', expected e.g. '7:00 pm' or '19:00'"))
+}
+
+impl Schedule {
+    #[cfg(test)]
+    pub(crate) fn new(run_at: &[&str]) -> Result<Self> {
+        Self::try_from(RawSchedule {
+            run_at: run_at.iter().map(ToString::to_string).collect(),
+        })
+    }
+
+    pub(crate) fn next_run_after<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> DateTime<Tz> {
+        let today = now.date_naive();
+        // Looking up to two days ahead also covers run times skipped because of DST change.
+        (0..=2)
+            .flat_map(|day| self.run_at.iter().map(move |time| (day, time)))
+            .filter_map(|(day, time)| {
+                let date = today + Duration::days(day);
+                now.timezone()
+                    .from_local_datetime(&date.and_time(*time))
+                    .earliest()
+            })
+            .find(|candidate| candidate > now)
+            .expect("schedule always has a run time within next days")
+    }
+}
+
+pub(crate) fn run_daemon<F>(schedule: &Schedule, mut check: F) -> Result<()>
+where
+    F: FnMut() -> Result<()>,
+{
+    info!("running as a daemon with schedule: {schedule:?}");
+    loop {
+        let next_run = schedule.next_run_after(&Local::now());
+        info!("next games check at {next_run}");
+        sleep_until(&next_run);
+        if let Err(e) = check() {
+            error!("games check failed, waiting for the next run: {e:?}");
+        }
+    }
+}
+
+fn sleep_until(time: &DateTime<Local>) {
+    loop {
+        let remaining = *time - Local::now();
+        if remaining <= Duration::zero() {
+            return;
+        }
+        let chunk = remaining.min(Duration::seconds(MAX_SLEEP_CHUNK_SECS));
+        debug!("sleeping for {}s", chunk.num_seconds());
+        thread::sleep(chunk.to_std().unwrap_or_default());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testutils;
+    use chrono::Utc;
+
+    fn at(time: &str) -> DateTime<Utc> {
+        Utc.from_utc_datetime(
+            &chrono::NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_parse_time_with_12h_format() {
+        testutils::setup_logger();
+        // given
+        let times = ["7:00 pm", "07:00 PM", "7:00pm", "12:30 am"];
+
+        // when
+        let parsed = times
+            .iter()
+            .map(|time| parse_time(time).unwrap())
+            .collect::<Vec<_>>();
+
+        // then
+        assert_eq!(
+            parsed,
+            vec![
+                NaiveTime::from_hms_opt(19, 0, 0).unwrap(),
+                NaiveTime::from_hms_opt(19, 0, 0).unwrap(),
+                NaiveTime::from_hms_opt(19, 0, 0).unwrap(),
+                NaiveTime::from_hms_opt(0, 30, 0).unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_time_with_24h_format() {
+        testutils::setup_logger();
+        // given
+        let time = "19:45";
+
+        // when
+        let parsed = parse_time(time).unwrap();
+
+        // then
+        assert_eq!(parsed, NaiveTime::from_hms_opt(19, 45, 0).unwrap());
+    }
+
+    #[test]
+    fn test_parse_time_with_invalid_time() {
+        testutils::setup_logger();
+        // given
+        let time = "25:00";
+
+        // when
+        let parsed = parse_time(time);
+
+        // then
+        assert!(parsed.is_err());
+    }
+
+    #[test]
+    fn test_schedule_without_times_is_invalid() {
+        testutils::setup_logger();
+        // given
+        let run_at = [];
+
+        // when
+        let schedule = Schedule::new(&run_at);
+
+        // then
+        assert!(schedule.is_err());
+    }
+
+    #[test]
+    fn test_next_run_later_the_same_day() {
+        testutils::setup_logger();
+        // given
+        let schedule = Schedule::new(&["7:00 pm", "8:00 am"]).unwrap();
+
+        // when
+        let next_run = schedule.next_run_after(&at("2022-12-20 09:15"));
+
+        // then
+        assert_eq!(next_run, at("2022-12-20 19:00"));
+    }
+
+    #[test]
+    fn test_next_run_the_next_day() {
+        testutils::setup_logger();
+        // given
+        let schedule = Schedule::new(&["7:00 pm", "8:00 am"]).unwrap();
+
+        // when
+        let next_run = schedule.next_run_after(&at("2022-12-31 19:00"));
+
+        // then
+        assert_eq!(next_run, at("2023-01-01 08:00"));
+    }
+}
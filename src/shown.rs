@@ -0,0 +1,136 @@
+use crate::configuration::sweetch_dir;
+use crate::json_file::JsonFile;
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+// ids are given by the notification server, which can hand them to other notifications after
+// it's restarted, so old ones are not reused
+const FORGET_AFTER_HOURS: i64 = 24;
+
+pub(crate) fn shown_notifications_path() -> PathBuf {
+    sweetch_dir().join("shown_notifications.json")
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+struct ShownNotification {
+    id: u32,
+    shown_at: DateTime<Utc>,
+}
+
+/// Ids of shown notifications by their replacement key, so notifications of the next runs replace
+/// them instead of stacking, also when each run is a separate process.
+#[derive(Debug)]
+pub(crate) struct ShownNotifications {
+    file: JsonFile,
+    notifications: HashMap<String, ShownNotification>,
+}
+
+impl ShownNotifications {
+    pub(crate) fn load<P: Into<PathBuf>>(path: P) -> Result<Self> {
+        let file = JsonFile::new(path);
+        let notifications = file.load()?;
+        Ok(Self {
+            file,
+            notifications,
+        })
+    }
+
+    pub(crate) fn save(&self) -> Result<()> {
+        self.file.save(&self.notifications)
+    }
+
+    /// Id of the notification to replace, `None` if there's no recent one with the key.
+    pub(crate) fn id(&self, key: &str) -> Option<u32> {
+        self.id_at(key, Utc::now())
+    }
+
+    fn id_at(&self, key: &str, now: DateTime<Utc>) -> Option<u32> {
+        let result = self
+            .notifications
+            .get(key)
+            .filter(|shown| now - shown.shown_at < Duration::hours(FORGET_AFTER_HOURS))
+            .map(|shown| shown.id);
+        debug!("notification to replace for '{key}': {result:?}");
+        result
+    }
+
+    pub(crate) fn insert(&mut self, key: String, id: u32) {
+        self.insert_at(key, id, Utc::now());
+    }
+
+    /// Remembers the notification. Expired ones are forgotten.
+    fn insert_at(&mut self, key: String, id: u32, now: DateTime<Utc>) {
+        self.notifications
+            .retain(|_, shown| now - shown.shown_at < Duration::hours(FORGET_AFTER_HOURS));
+        self.notifications
+            .insert(key, ShownNotification { id, shown_at: now });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testutils::{self, day, load_in_temp_dir};
+    use tempfile::TempDir;
+
+    fn hour(hour: i64) -> DateTime<Utc> {
+        day(1) + Duration::hours(hour)
+    }
+
+    fn empty_shown() -> (TempDir, ShownNotifications) {
+        load_in_temp_dir("shown_notifications.json", ShownNotifications::load)
+    }
+
+    #[test]
+    fn test_recent_notification_is_replaced() {
+        testutils::setup_logger();
+        // given
+        let (_dir, mut shown) = empty_shown();
+
+        // when
+        shown.insert_at("Game 1".into(), 7, hour(0));
+
+        // then
+        assert_eq!(shown.id_at("Game 1", hour(23)), Some(7));
+        assert_eq!(shown.id_at("Game 1", hour(24)), None);
+        assert_eq!(shown.id_at("Game 2", hour(0)), None);
+    }
+
+    #[test]
+    fn test_expired_notifications_are_forgotten() {
+        testutils::setup_logger();
+        // given
+        let (_dir, mut shown) = empty_shown();
+        shown.insert_at("Game 1".into(), 7, hour(0));
+
+        // when
+        shown.insert_at("Game 2".into(), 8, hour(30));
+
+        // then
+        assert_eq!(
+            shown.notifications.keys().collect::<Vec<_>>(),
+            vec!["Game 2"]
+        );
+    }
+
+    #[test]
+    fn test_shown_notifications_are_persisted() {
+        testutils::setup_logger();
+        // given
+        let (dir, mut shown) = empty_shown();
+        shown.insert_at("Game 1".into(), 7, hour(0));
+        shown.insert_at("combined".into(), 8, hour(1));
+
+        // when
+        shown.save().unwrap();
+        let loaded = ShownNotifications::load(dir.path().join("shown_notifications.json")).unwrap();
+
+        // then
+        assert_eq!(loaded.notifications, shown.notifications);
+    }
+}
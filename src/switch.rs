@@ -1,65 +1,353 @@
-use crate::entities::WatchedGame;
-
-use anyhow::Result;
-use log::{debug, error, info};
+use crate::configuration::{Rule, RuleInput};
+use crate::data_providers::{Capabilities, GameProvider};
+use crate::entities::{Criteria, Region, WatchedGame};
+use crate::matching::title_matches;
+use crate::muted::MutedGames;
+
+use anyhow::{anyhow, Error, Result};
+use chrono::{DateTime, Utc};
+use log::{debug, error, info, warn};
 use serde::Deserialize;
-
-pub(crate) fn acceptable_games<F>(watched_games: &[WatchedGame], games_provider: F) -> Vec<Game>
+use std::collections::HashMap;
+use std::iter;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Games matching the criteria of watched games, together with watched games which couldn't be
+/// fetched.
+pub(crate) fn acceptable_games<P>(watched_games: &[WatchedGame], provider: &P) -> CheckResult
 where
-    F: Fn(String) -> Result<Vec<Game>>,
+    P: GameProvider + ?Sized,
 {
-    info!("checking games on sale");
-    let mut games = Vec::new();
-    for watched_game in watched_games {
-        let found_games = match games_provider(watched_game.title()) {
+    info!("checking games on sale in {}", provider.name());
+    let capabilities = provider.capabilities();
+    let batches = batches(watched_games, provider);
+    let fetched = fetch_concurrently(&batches, provider);
+    let mut found = vec![Vec::new(); watched_games.len()];
+    let mut failures = Vec::new();
+    for (batch, result) in batches.into_iter().zip(fetched) {
+        let found_games = match result {
             Ok(games) => games,
-            Err(e) => {
-                error!("failed to fetch games: {}", e);
-                vec![]
+            Err(error) => {
+                for (position, watched_game) in batch.indices.into_iter().zip(batch.games) {
+                    error!("failed to fetch '{}': {error}", watched_game.title());
+                    failures.push(FetchFailure {
+                        watched_game,
+                        position,
+                        error: anyhow!("{error:#}"),
+                    });
+                }
+                continue;
             }
         };
         debug!("found games: {:#?}", &found_games);
-        games.extend(filter(found_games, watched_game));
+        for (idx, watched_game) in batch.indices.into_iter().zip(&batch.games) {
+            found[idx] = filter(found_games.clone(), watched_game, capabilities);
+        }
+    }
+    // keeps the order of watched games, regardless of how they were batched
+    let positions = found
+        .iter()
+        .enumerate()
+        .flat_map(|(position, games)| iter::repeat_n(position, games.len()))
+        .collect::<Vec<_>>();
+    let games = with_sale_ends(found.into_iter().flatten().collect(), provider);
+    CheckResult {
+        games: positions.into_iter().zip(games).collect(),
+        failures,
+        checked: watched_games.len(),
+    }
+}
+
+/// Adds ends of sales to discounted games. Games are notified without them when the store can't
+/// be asked.
+fn with_sale_ends<P>(games: Vec<Game>, provider: &P) -> Vec<Game>
+where
+    P: GameProvider + ?Sized,
+{
+    let mut sale_ends = HashMap::new();
+    for region in provider.regions() {
+        let nsuids = games
+            .iter()
+            .filter(|game| game.region() == Some(*region) && game.discount_percent().is_some())
+            .flat_map(|game| game.nsuids().iter().cloned())
+            .collect::<Vec<_>>();
+        if nsuids.is_empty() {
+            continue;
+        }
+        match provider.sale_ends(&nsuids, *region) {
+            Ok(ends) => {
+                sale_ends.extend(ends.into_iter().map(|(nsuid, end)| ((nsuid, *region), end)));
+            }
+            Err(e) => warn!("failed to fetch sale ends in region {region}: {e:#}"),
+        }
     }
     games
+        .into_iter()
+        .map(|game| {
+            let sale_end = game.region().and_then(|region| {
+                game.nsuids()
+                    .iter()
+                    .find_map(|nsuid| sale_ends.get(&(nsuid.clone(), region)).copied())
+            });
+            game.with_sale_end(sale_end)
+        })
+        .collect()
 }
 
-pub(crate) fn fetch<S: Into<String>>(title: S) -> Result<Vec<Game>> {
-    let root = ureq::get(&build_url(title)).call()?.into_json::<Root>()?;
-    Ok(root.response.docs)
+/// Fetches batches using at most [`GameProvider::concurrency`] threads. Results are in the order
+/// of batches, no matter which one was fetched first.
+fn fetch_concurrently<P>(batches: &[Batch], provider: &P) -> Vec<Result<Vec<Game>>>
+where
+    P: GameProvider + ?Sized,
+{
+    let workers = provider.concurrency().clamp(1, batches.len().max(1));
+    debug!("fetching {} batches with {workers} workers", batches.len());
+    let next_batch = AtomicUsize::new(0);
+    let results = Mutex::new(batches.iter().map(|_| None).collect::<Vec<_>>());
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let idx = next_batch.fetch_add(1, Ordering::Relaxed);
+                let Some(batch) = batches.get(idx) else {
+                    break;
+                };
+                debug!(
+                    "fetching {} games in region {} (prices in {})",
+                    batch.games.len(),
+                    batch.region,
+                    batch.region.currency()
+                );
+                let result = provider.fetch_batch(&batch.games, batch.region);
+                results.lock().expect("poisoned lock")[idx] = Some(result);
+            });
+        }
+    });
+    results
+        .into_inner()
+        .expect("poisoned lock")
+        .into_iter()
+        .map(|result| result.expect("all batches are fetched"))
+        .collect()
 }
 
-fn build_url<S: Into<String>>(title: S) -> String {
-    let title = title.into();
-    let title_normalized = title.replace(':', "\\:"); // normalization because of solr used underneath
+/// Watched games fetched together, all of them in the same region.
+struct Batch {
+    region: Region,
+    /// Positions of the games in the watch list.
+    indices: Vec<usize>,
+    games: Vec<WatchedGame>,
+}
 
-    let url = format!(
-        "http://search.nintendo-europe.com/en/select?rows=99\
-        &fq=type:GAME%20AND%20system_type:nintendoswitch*%20AND\
-        %20product_code_txt:*%20AND%20title:{title_normalized}&\
-        q={title}&sort=sorting_title%20asc&start=0&wt=json"
-    );
-    debug!("built url: {}", url);
-    url
+/// Splits watched games into batches no bigger than the provider can fetch at once. Games with
+/// a pattern get a batch of their own, as their broad searches could push other games out of
+/// the results.
+fn batches<P>(watched_games: &[WatchedGame], provider: &P) -> Vec<Batch>
+where
+    P: GameProvider + ?Sized,
+{
+    let batch_size = provider.capabilities().batch_size.max(1);
+    let mut batches: Vec<Batch> = Vec::new();
+    for (idx, watched_game) in watched_games.iter().enumerate() {
+        let region = provider.region_for(watched_game);
+        let open_batch = batches.iter_mut().find(|batch| {
+            batch.region == region
+                && batch.indices.len() < batch_size
+                && watched_game.pattern().is_none()
+                && batch.games.iter().all(|game| game.pattern().is_none())
+        });
+        match open_batch {
+            Some(batch) => {
+                batch.indices.push(idx);
+                batch.games.push(watched_game.clone());
+            }
+            None => batches.push(Batch {
+                region,
+                indices: vec![idx],
+                games: vec![watched_game.clone()],
+            }),
+        }
+    }
+    batches
+}
+
+#[derive(Debug)]
+pub(crate) struct CheckResult {
+    /// Found games with the position of their watched game in the checked list.
+    games: Vec<(usize, Game)>,
+    failures: Vec<FetchFailure>,
+    checked: usize,
+}
+
+impl CheckResult {
+    pub(crate) fn games(&self) -> Vec<Game> {
+        self.games.iter().map(|(_, game)| game.clone()).collect()
+    }
+
+    pub(crate) fn has_games(&self) -> bool {
+        !self.games.is_empty()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn into_games(self) -> Vec<Game> {
+        self.games.into_iter().map(|(_, game)| game).collect()
+    }
+
+    pub(crate) fn failures(&self) -> &[FetchFailure] {
+        &self.failures
+    }
+
+    /// Number of watched games which were checked.
+    pub(crate) fn checked(&self) -> usize {
+        self.checked
+    }
+
+    pub(crate) fn has_failures(&self) -> bool {
+        !self.failures.is_empty()
+    }
+
+    pub(crate) fn all_failed(&self) -> bool {
+        self.has_failures() && self.failures.len() == self.checked
+    }
+
+    pub(crate) fn failed_games(&self) -> Vec<WatchedGame> {
+        self.failures
+            .iter()
+            .map(|failure| failure.watched_game.clone())
+            .collect()
+    }
+
+    /// Drops games which the user snoozed or already bought.
+    pub(crate) fn without_muted(mut self, muted: &MutedGames) -> Self {
+        let found = self.games.len();
+        self.games.retain(|(_, game)| !muted.is_muted(game));
+        if self.games.len() < found {
+            info!("skipping {} muted games", found - self.games.len());
+        }
+        self
+    }
+
+    /// Combines with the result of retrying failed games, keeping games in the order of watched
+    /// games, so the notification looks the same as without the retry.
+    pub(crate) fn merge_retry(mut self, retry: CheckResult) -> Self {
+        // retry checked failed games, its positions are positions among them
+        let positions = self
+            .failures
+            .iter()
+            .map(|failure| failure.position)
+            .collect::<Vec<_>>();
+        self.games.extend(
+            retry
+                .games
+                .into_iter()
+                .map(|(position, game)| (positions[position], game)),
+        );
+        self.games.sort_by_key(|(position, _)| *position);
+        self.failures = retry
+            .failures
+            .into_iter()
+            .map(|failure| FetchFailure {
+                position: positions[failure.position],
+                ..failure
+            })
+            .collect();
+        self
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct FetchFailure {
+    watched_game: WatchedGame,
+    /// Position of the watched game in the checked list.
+    position: usize,
+    error: Error,
+}
+
+impl FetchFailure {
+    pub(crate) fn watched_game(&self) -> &WatchedGame {
+        &self.watched_game
+    }
+
+    pub(crate) fn error(&self) -> &Error {
+        &self.error
+    }
 }
 
-fn filter(games: Vec<Game>, watched_game: &WatchedGame) -> Vec<Game> {
+fn filter(games: Vec<Game>, watched_game: &WatchedGame, capabilities: Capabilities) -> Vec<Game> {
     debug!("filtering by title: {}", watched_game.title());
     games
         .into_iter()
-        .filter(|game| titles_match(game, watched_game))
-        .filter(|game| {
-            if watched_game.acceptable_price().is_some() {
-                price_acceptable(game, watched_game)
-            } else {
-                is_on_sale(game)
-            }
-        })
+        .filter(|game| is_watched_game(game, watched_game))
+        .filter(|game| meets_criteria(game, watched_game, capabilities))
+        .filter(|game| near_all_time_low(game, watched_game))
         .collect::<Vec<Game>>()
 }
 
+/// Checks price criteria of the watched game, combined as its [`Criteria`] says. Without any
+/// criteria, the game has to be on sale.
+fn meets_criteria(game: &Game, watched_game: &WatchedGame, capabilities: Capabilities) -> bool {
+    let mut results = Vec::new();
+    if watched_game.acceptable_price().is_some() {
+        results.push(price_acceptable(game, watched_game));
+    }
+    if let Some(min_discount_percent) = watched_game.min_discount_percent() {
+        results.push(discount_acceptable(game, min_discount_percent));
+    }
+    if let Some(rule) = watched_game.rule() {
+        results.push(rule_matches(game, rule, capabilities));
+    }
+    if watched_game.notify_on_price_drop() {
+        results.push(price_dropped(game));
+    }
+    if results.is_empty() {
+        return is_on_sale(game, capabilities);
+    }
+    match watched_game.criteria() {
+        Criteria::All => results.iter().all(|result| *result),
+        Criteria::Any => results.iter().any(|result| *result),
+    }
+}
+
+/// Watched games with a store ID are recognized by it, so renamed games and other editions with
+/// the same title don't get mixed up. Others are recognized by the title.
+pub(crate) fn is_watched_game(game: &Game, watched_game: &WatchedGame) -> bool {
+    if watched_game.has_store_id() {
+        ids_match(game, watched_game)
+    } else {
+        titles_match(game, watched_game)
+    }
+}
+
+fn ids_match(game: &Game, watched_game: &WatchedGame) -> bool {
+    store_ids_match(&game.nsuid_txt, &game.product_code_txt, watched_game)
+}
+
+/// Checks store IDs of a found game against those set for the watched game.
+pub(crate) fn store_ids_match(
+    nsuids: &[String],
+    product_codes: &[String],
+    watched_game: &WatchedGame,
+) -> bool {
+    let nsuid_matches = watched_game
+        .nsuid()
+        .is_none_or(|nsuid| nsuids.iter().any(|id| id == nsuid));
+    let product_code_matches = watched_game.product_code().is_none_or(|product_code| {
+        product_codes
+            .iter()
+            .any(|code| code.eq_ignore_ascii_case(product_code))
+    });
+    let result = nsuid_matches && product_code_matches;
+    debug!(
+        "checking ids match: [{nsuids:?}, {product_codes:?}] and [{:?}, {:?}] => {result}",
+        watched_game.nsuid(),
+        watched_game.product_code()
+    );
+    result
+}
+
 fn titles_match(game: &Game, watched_game: &WatchedGame) -> bool {
-    let result = game.title() == watched_game.title();
+    let result = title_matches(&game.title, watched_game);
     debug!(
         "checking titles match: [{}] and [{}] => {}",
         game.title(),
@@ -87,19 +375,53 @@ fn check_price_acceptable(game: &Game, price: f64) -> bool {
     game.lowest_price() <= price
 }
 
-fn is_on_sale(game: &Game) -> bool {
-    debug!("filtering by 'is on sale': {}", game.is_on_sale());
-    game.is_on_sale()
+fn discount_acceptable(game: &Game, min_discount_percent: f64) -> bool {
+    let discount = game.discount_percent().unwrap_or(0.0);
+    debug!("filtering by discount: checking {discount:.0}% >= {min_discount_percent}%");
+    discount >= min_discount_percent
+}
+
+fn price_dropped(game: &Game) -> bool {
+    let price_drop = game.price_drop();
+    debug!("filtering by price drop: {price_drop:?}");
+    price_drop.is_some()
+}
+
+/// Restricts games to prices close to the lowest recorded one, on top of other criteria.
+fn near_all_time_low(game: &Game, watched_game: &WatchedGame) -> bool {
+    let Some(max_percent_above_low) = watched_game.max_percent_above_low() else {
+        return true;
+    };
+    let result = game.is_within_percent_of_low(max_percent_above_low);
+    debug!(
+        "filtering by all-time low: checking {} within {max_percent_above_low}% of {:?} => {result}",
+        game.lowest_price(),
+        game.lowest_recorded_price()
+    );
+    result
 }
 
-#[derive(Deserialize)]
-struct Root {
-    response: Response,
+fn rule_matches(game: &Game, rule: &Rule, capabilities: Capabilities) -> bool {
+    let input = RuleInput {
+        price: game.lowest_price(),
+        regular_price: game.price_regular(),
+        discount: game.discount_percent().unwrap_or(0.0),
+        on_sale: is_on_sale(game, capabilities),
+        all_time_low: game.is_all_time_low(),
+    };
+    let result = rule.evaluate(&input);
+    debug!("filtering by rule '{}': {result}", rule.source());
+    result
 }
 
-#[derive(Deserialize)]
-struct Response {
-    docs: Vec<Game>,
+fn is_on_sale(game: &Game, capabilities: Capabilities) -> bool {
+    let result = if capabilities.sale_flag {
+        game.is_on_sale()
+    } else {
+        game.has_discounted_price()
+    };
+    debug!("filtering by 'is on sale': {result}");
+    result
 }
 
 #[derive(Deserialize, Default, Debug, PartialEq, Clone)]
@@ -109,6 +431,26 @@ pub(crate) struct Game {
     // not all entries in API have a price
     price_regular_f: Option<f64>,
     price_has_discount_b: Option<bool>,
+    price_discount_percentage_f: Option<f64>,
+    #[serde(default)]
+    nsuid_txt: Vec<String>,
+    #[serde(default)]
+    product_code_txt: Vec<String>,
+    /// Store page, relative to the store site until the provider resolves it.
+    url: Option<String>,
+    /// Square box art, protocol-relative in Nintendo Europe search.
+    image_url_sq_s: Option<String>,
+    #[serde(skip)]
+    region: Option<Region>,
+    /// Lowest price from previous checks, `None` if the game wasn't seen before.
+    #[serde(skip)]
+    lowest_recorded_price: Option<f64>,
+    /// Price from the previous check, `None` if the game wasn't seen before.
+    #[serde(skip)]
+    last_recorded_price: Option<f64>,
+    /// End of the current sale, `None` if the store doesn't tell.
+    #[serde(skip)]
+    sale_end: Option<DateTime<Utc>>,
 }
 
 impl Game {
@@ -120,129 +462,216 @@ impl Game {
         }
     }
 
+    pub(crate) fn with_prices<S: Into<String>>(
+        title: S,
+        price_regular: Option<f64>,
+        price_discounted: Option<f64>,
+    ) -> Self {
+        Self {
+            title: title.into(),
+            price_regular_f: price_regular,
+            price_discounted_f: price_discounted,
+            price_has_discount_b: None,
+            nsuid_txt: Vec::new(),
+            product_code_txt: Vec::new(),
+            region: None,
+            lowest_recorded_price: None,
+            last_recorded_price: None,
+            sale_end: None,
+            url: None,
+            image_url_sq_s: None,
+            price_discount_percentage_f: None,
+        }
+    }
+
+    pub(crate) fn with_nsuid<S: Into<String>>(mut self, nsuid: S) -> Self {
+        self.nsuid_txt = vec![nsuid.into()];
+        self
+    }
+
+    pub(crate) fn in_region(mut self, region: Region) -> Self {
+        self.region = Some(region);
+        self
+    }
+
+    pub(crate) fn with_lowest_recorded_price(mut self, price: Option<f64>) -> Self {
+        self.lowest_recorded_price = price;
+        self
+    }
+
+    pub(crate) fn with_last_recorded_price(mut self, price: Option<f64>) -> Self {
+        self.last_recorded_price = price;
+        self
+    }
+
+    pub(crate) fn with_sale_end(mut self, sale_end: Option<DateTime<Utc>>) -> Self {
+        self.sale_end = sale_end;
+        self
+    }
+
+    /// Resolves the store page relative to given site.
+    pub(crate) fn with_site(mut self, site: &str) -> Self {
+        self.url = self.url.map(|url| {
+            if url.starts_with('/') {
+                format!("{site}{url}")
+            } else {
+                url
+            }
+        });
+        self
+    }
+
+    pub(crate) fn with_url<S: Into<String>>(mut self, url: S) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_image_url<S: Into<String>>(mut self, image_url: S) -> Self {
+        self.image_url_sq_s = Some(image_url.into());
+        self
+    }
+
     pub(crate) fn title(&self) -> String {
         self.title.clone()
     }
 
-    fn lowest_price(&self) -> f64 {
-        let price_regular = self.price_regular_f.unwrap_or(f64::MAX);
-        match self.price_discounted_f {
-            Some(discounted_price) => discounted_price.min(price_regular),
-            None => price_regular,
-        }
+    pub(crate) fn region(&self) -> Option<Region> {
+        self.region
     }
 
-    fn is_on_sale(&self) -> bool {
-        self.price_has_discount_b.unwrap_or(false)
+    pub(crate) fn nsuids(&self) -> &[String] {
+        &self.nsuid_txt
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use crate::testutils;
+    pub(crate) fn product_codes(&self) -> &[String] {
+        &self.product_code_txt
+    }
 
-    #[test]
-    fn test_build_url_with_space() {
-        testutils::setup_logger();
-        // given
-        let title = "Test title";
+    pub(crate) fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
 
-        // when
-        let url = build_url(title);
+    pub(crate) fn image_url(&self) -> Option<String> {
+        self.image_url_sq_s.as_ref().map(|image_url| {
+            if image_url.starts_with("//") {
+                format!("https:{image_url}")
+            } else {
+                image_url.clone()
+            }
+        })
+    }
 
-        // then
-        assert_eq!(
-            url,
-            "http://search.nintendo-europe.com/en/select?rows=99\
-            &fq=type:GAME%20AND%20system_type:nintendoswitch*%20AND\
-            %20product_code_txt:*%20AND%20title:Test title&q=Test title\
-            &sort=sorting_title%20asc&start=0&wt=json"
-        );
+    pub(crate) fn price_regular(&self) -> Option<f64> {
+        self.price_regular_f
     }
 
-    #[test]
-    fn test_build_url_with_colon() {
-        testutils::setup_logger();
-        // given
-        let title = "Test:title";
+    pub(crate) fn price_discounted(&self) -> Option<f64> {
+        self.price_discounted_f
+    }
 
-        // when
-        let url = build_url(title);
+    pub(crate) fn has_discount(&self) -> Option<bool> {
+        self.price_has_discount_b
+    }
 
-        // then
-        assert_eq!(
-            url,
-            "http://search.nintendo-europe.com/en/select?rows=99\
-            &fq=type:GAME%20AND%20system_type:nintendoswitch*%20AND\
-            %20product_code_txt:*%20AND%20title:Test\\:title&q=Test:title\
-            &sort=sorting_title%20asc&start=0&wt=json"
-        );
+    pub(crate) fn lowest_price(&self) -> f64 {
+        let price_regular = self.price_regular_f.unwrap_or(f64::MAX);
+        match self.price_discounted_f {
+            Some(discounted_price) => discounted_price.min(price_regular),
+            None => price_regular,
+        }
     }
 
-    #[test]
-    fn test_build_url_with_special_chars() {
-        testutils::setup_logger();
-        // given
-        let title = "Test!@#$%^&*()-=[]\\;',./<>?:\"{}|+_";
+    /// Discount given by the store or computed from the prices, `None` when the game is not
+    /// discounted.
+    pub(crate) fn discount_percent(&self) -> Option<f64> {
+        if let Some(percent) = self
+            .price_discount_percentage_f
+            .filter(|percent| *percent > 0.0)
+        {
+            return Some(percent);
+        }
+        match (self.price_regular_f, self.price_discounted_f) {
+            (Some(regular), Some(discounted)) if discounted < regular && regular > 0.0 => {
+                Some((regular - discounted) / regular * 100.0)
+            }
+            _ => None,
+        }
+    }
 
-        // when
-        let url = build_url(title);
+    pub(crate) fn sale_end(&self) -> Option<DateTime<Utc>> {
+        self.sale_end
+    }
 
-        // then
-        assert_eq!(
-            url,
-            "http://search.nintendo-europe.com/en/select?rows=99\
-            &fq=type:GAME%20AND%20system_type:nintendoswitch*%20AND\
-            %20product_code_txt:*%20AND%20title:Test!@#$%^&*()-=[]\\;\
-            ',./<>?\\:\"{}|+_&q=Test!@#$%^&*()-=[]\\;',./<>?:\"{}|+_\
-            &sort=sorting_title%20asc&start=0&wt=json"
-        );
+    pub(crate) fn lowest_recorded_price(&self) -> Option<f64> {
+        self.lowest_recorded_price
     }
 
-    #[test]
-    fn test_build_url_with_empty_title() {
-        testutils::setup_logger();
-        // given
-        let title = "";
+    /// Current price is not higher than any price seen before. Games seen for the first time have
+    /// no history to compare with.
+    pub(crate) fn is_all_time_low(&self) -> bool {
+        self.is_within_percent_of_low(0.0)
+    }
 
-        // when
-        let url = build_url(title);
+    /// Current price is at most given percent above the lowest price seen before.
+    pub(crate) fn is_within_percent_of_low(&self, percent: f64) -> bool {
+        self.lowest_recorded_price
+            .is_some_and(|lowest| self.lowest_price() <= lowest * (1.0 + percent / 100.0))
+    }
 
-        // then
-        assert_eq!(
-            url,
-            "http://search.nintendo-europe.com/en/select?rows=99\
-            &fq=type:GAME%20AND%20system_type:nintendoswitch*%20AND\
-            %20product_code_txt:*%20AND%20title:&q=&sort=sorting_title\
-            %20asc&start=0&wt=json"
-        );
+    /// Current price is lower than any price seen before.
+    pub(crate) fn is_new_all_time_low(&self) -> bool {
+        self.lowest_recorded_price
+            .is_some_and(|lowest| self.lowest_price() < lowest)
+    }
+
+    /// Previous and current price, if the price went down since the previous check.
+    pub(crate) fn price_drop(&self) -> Option<(f64, f64)> {
+        let price = self.lowest_price();
+        self.last_recorded_price
+            .filter(|last| price < *last)
+            .map(|last| (last, price))
+    }
+
+    fn is_on_sale(&self) -> bool {
+        self.price_has_discount_b.unwrap_or(false)
     }
 
+    fn has_discounted_price(&self) -> bool {
+        match (self.price_discounted_f, self.price_regular_f) {
+            (Some(discounted_price), Some(regular_price)) => discounted_price < regular_price,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::entities::PatternSyntax;
+    use crate::testutils::{self, StubProvider};
+    use chrono::TimeZone;
+
     #[test]
     fn test_acceptable_games_with_empty_games_list() {
         testutils::setup_logger();
         // given
-        let games_provider = provider_with_ok_result(vec![]);
+        let games_provider = StubProvider::with_games(vec![]);
 
         let watched_games = vec![WatchedGame::new("Game 1").with_acceptable_price(1.0)];
 
         // when
-        let filtered_games = acceptable_games(&watched_games, games_provider);
+        let filtered_games = acceptable_games(&watched_games, &games_provider).into_games();
 
         // then
         assert_eq!(filtered_games, vec![]);
     }
 
-    fn provider_with_ok_result(result: Vec<Game>) -> Box<dyn Fn(String) -> Result<Vec<Game>>> {
-        Box::new(move |_| -> Result<Vec<Game>> { Ok(result.clone()) })
-    }
-
     #[test]
     fn test_acceptable_price_with_title_not_matching() {
         testutils::setup_logger();
         // given
-        let games_provider = provider_with_ok_result(vec![
+        let games_provider = StubProvider::with_games(vec![
             Game {
                 title: "Game 1".into(),
                 ..Game::default()
@@ -253,7 +682,7 @@ mod test {
         let watched_games = vec![WatchedGame::new("Game 3")];
 
         // when
-        let filtered_games = acceptable_games(&watched_games, games_provider);
+        let filtered_games = acceptable_games(&watched_games, &games_provider).into_games();
 
         // then
         assert_eq!(filtered_games, vec![]);
@@ -263,7 +692,7 @@ mod test {
     fn test_acceptable_games_with_regular_price_acceptable() {
         testutils::setup_logger();
         // given
-        let games_provider = provider_with_ok_result(vec![
+        let games_provider = StubProvider::with_games(vec![
             Game {
                 title: "Game 1".into(),
                 price_regular_f: Some(7.0),
@@ -275,7 +704,7 @@ mod test {
         let watched_games = vec![WatchedGame::new("Game 1").with_acceptable_price(10.0)];
 
         // when
-        let filtered_games = acceptable_games(&watched_games, games_provider);
+        let filtered_games = acceptable_games(&watched_games, &games_provider).into_games();
 
         // then
         assert_eq!(
@@ -292,7 +721,7 @@ mod test {
     fn test_acceptable_games_with_discounted_price_acceptable() {
         testutils::setup_logger();
         // given
-        let games_provider = provider_with_ok_result(vec![
+        let games_provider = StubProvider::with_games(vec![
             Game {
                 title: "Game 1".into(),
                 price_discounted_f: Some(5.0),
@@ -304,7 +733,7 @@ mod test {
         let watched_games = vec![WatchedGame::new("Game 1").with_acceptable_price(10.0)];
 
         // when
-        let filtered_games = acceptable_games(&watched_games, games_provider);
+        let filtered_games = acceptable_games(&watched_games, &games_provider).into_games();
 
         // then
         assert_eq!(
@@ -321,7 +750,7 @@ mod test {
     fn test_acceptable_games_with_game_which_is_on_sale() {
         testutils::setup_logger();
         // given
-        let games_provider = provider_with_ok_result(vec![
+        let games_provider = StubProvider::with_games(vec![
             Game {
                 title: "Game 1".into(),
                 price_has_discount_b: Some(true),
@@ -333,7 +762,7 @@ mod test {
         let watched_games = vec![WatchedGame::new("Game 1")];
 
         // when
-        let filtered_games = acceptable_games(&watched_games, games_provider);
+        let filtered_games = acceptable_games(&watched_games, &games_provider).into_games();
 
         // then
         assert_eq!(
@@ -350,7 +779,7 @@ mod test {
     fn test_acceptable_games_without_acceptable_price_but_with_discount() {
         testutils::setup_logger();
         // given
-        let games_provider = provider_with_ok_result(vec![
+        let games_provider = StubProvider::with_games(vec![
             Game {
                 title: "Game 1".into(),
                 price_has_discount_b: Some(true),
@@ -363,7 +792,7 @@ mod test {
         let watched_games = vec![WatchedGame::new("Game 1")];
 
         // when
-        let filtered_games = acceptable_games(&watched_games, games_provider);
+        let filtered_games = acceptable_games(&watched_games, &games_provider).into_games();
 
         // then
         assert_eq!(
@@ -381,7 +810,7 @@ mod test {
     fn test_acceptable_games_with_acceptable_price_and_without_discount() {
         testutils::setup_logger();
         // given
-        let games_provider = provider_with_ok_result(vec![
+        let games_provider = StubProvider::with_games(vec![
             Game {
                 title: "Game 1".into(),
                 price_has_discount_b: Some(false),
@@ -394,7 +823,7 @@ mod test {
         let watched_games = vec![WatchedGame::new("Game 1").with_acceptable_price(10.0)];
 
         // when
-        let filtered_games = acceptable_games(&watched_games, games_provider);
+        let filtered_games = acceptable_games(&watched_games, &games_provider).into_games();
 
         // then
         assert_eq!(
@@ -412,25 +841,43 @@ mod test {
     fn test_acceptable_games_without_acceptable_price_and_without_discount() {
         testutils::setup_logger();
         // given
-        let games_provider = provider_with_ok_result(vec![
+        let games_provider = StubProvider::with_games(vec![
             Game {
                 title: "Game 1".into(),
                 price_discounted_f: Some(0.5),
                 price_regular_f: Some(7.0),
                 price_has_discount_b: Some(false),
+                nsuid_txt: Vec::new(),
+                product_code_txt: Vec::new(),
+                region: None,
+                lowest_recorded_price: None,
+                last_recorded_price: None,
+                sale_end: None,
+                url: None,
+                image_url_sq_s: None,
+                price_discount_percentage_f: None,
             },
             Game {
                 title: "Game 2".into(),
                 price_discounted_f: Some(7.0),
                 price_regular_f: Some(7.0),
                 price_has_discount_b: Some(false),
+                nsuid_txt: Vec::new(),
+                product_code_txt: Vec::new(),
+                region: None,
+                lowest_recorded_price: None,
+                last_recorded_price: None,
+                sale_end: None,
+                url: None,
+                image_url_sq_s: None,
+                price_discount_percentage_f: None,
             },
         ]);
 
         let watched_games = vec![WatchedGame::new("Game 1")];
 
         // when
-        let filtered_games = acceptable_games(&watched_games, games_provider);
+        let filtered_games = acceptable_games(&watched_games, &games_provider).into_games();
 
         // then
         assert_eq!(filtered_games, vec![]);
@@ -440,7 +887,7 @@ mod test {
     fn test_acceptable_games_with_games_with_discount_set_to_false() {
         testutils::setup_logger();
         // given
-        let games_provider = provider_with_ok_result(vec![
+        let games_provider = StubProvider::with_games(vec![
             Game {
                 title: "Game 1".into(),
                 price_has_discount_b: Some(false),
@@ -456,7 +903,7 @@ mod test {
         let watched_games = vec![WatchedGame::new("Game 1")];
 
         // when
-        let filtered_games = acceptable_games(&watched_games, games_provider);
+        let filtered_games = acceptable_games(&watched_games, &games_provider).into_games();
 
         // then
         assert_eq!(filtered_games, vec![]);
@@ -466,7 +913,7 @@ mod test {
     fn test_acceptable_games_with_games_without_discount_field() {
         testutils::setup_logger();
         // given
-        let games_provider = provider_with_ok_result(vec![
+        let games_provider = StubProvider::with_games(vec![
             Game {
                 title: "Game 1".into(),
                 price_has_discount_b: None,
@@ -482,7 +929,7 @@ mod test {
         let watched_games = vec![WatchedGame::new("Game 1")];
 
         // when
-        let filtered_games = acceptable_games(&watched_games, games_provider);
+        let filtered_games = acceptable_games(&watched_games, &games_provider).into_games();
 
         // then
         assert_eq!(filtered_games, vec![]);
@@ -492,14 +939,578 @@ mod test {
     fn test_acceptable_games_when_provider_returns_error() {
         testutils::setup_logger();
         // given
-        let games_provider = |_| -> Result<Vec<Game>> { anyhow::bail!("Some error") };
+        let games_provider = StubProvider::failing("Some error");
         let watched_games = vec![WatchedGame::new("Game 1")];
 
         // when
-        let filtered_games = acceptable_games(&watched_games, games_provider);
+        let result = acceptable_games(&watched_games, &games_provider);
 
         // then
-        assert_eq!(filtered_games, vec![]);
+        assert_eq!(result.games(), vec![]);
+        assert_eq!(result.failed_games(), watched_games);
+        assert!(result.all_failed());
+    }
+
+    #[test]
+    fn test_acceptable_games_with_partial_failure() {
+        testutils::setup_logger();
+        // given
+        let games_provider = StubProvider::with_games(vec![Game {
+            title: "Game 1".into(),
+            price_has_discount_b: Some(true),
+            ..Game::default()
+        }])
+        .failing_for("Game 2");
+        let watched_games = vec![WatchedGame::new("Game 1"), WatchedGame::new("Game 2")];
+
+        // when
+        let result = acceptable_games(&watched_games, &games_provider);
+
+        // then
+        assert_eq!(result.games().len(), 1);
+        assert_eq!(result.failed_games(), vec![WatchedGame::new("Game 2")]);
+        assert_eq!(result.checked(), 2);
+        assert!(!result.all_failed());
+    }
+
+    #[test]
+    fn test_acceptable_games_distributes_batch_results() {
+        testutils::setup_logger();
+        // given
+        let on_sale = |title: &str| Game {
+            title: title.into(),
+            price_has_discount_b: Some(true),
+            ..Game::default()
+        };
+        let games_provider =
+            StubProvider::with_games(vec![on_sale("Game 2"), on_sale("Game 1")]).with_batch_size(5);
+        let watched_games = vec![WatchedGame::new("Game 1"), WatchedGame::new("Game 2")];
+
+        // when
+        let filtered_games = acceptable_games(&watched_games, &games_provider).into_games();
+
+        // then
+        assert_eq!(filtered_games, vec![on_sale("Game 1"), on_sale("Game 2")]);
+    }
+
+    #[test]
+    fn test_acceptable_games_with_failed_batch() {
+        testutils::setup_logger();
+        // given
+        let games_provider = StubProvider::with_games(vec![])
+            .failing_for("Game 2")
+            .with_batch_size(2);
+        let watched_games = vec![
+            WatchedGame::new("Game 1"),
+            WatchedGame::new("Game 2"),
+            WatchedGame::new("Game 3"),
+        ];
+
+        // when
+        let result = acceptable_games(&watched_games, &games_provider);
+
+        // then
+        assert_eq!(
+            result.failed_games(),
+            vec![WatchedGame::new("Game 1"), WatchedGame::new("Game 2")]
+        );
+    }
+
+    #[test]
+    fn test_acceptable_games_fetched_concurrently_keep_order() {
+        testutils::setup_logger();
+        // given
+        let on_sale = |title: &str| Game {
+            title: title.into(),
+            price_has_discount_b: Some(true),
+            ..Game::default()
+        };
+        let titles = (0..12).map(|idx| format!("Game {idx}")).collect::<Vec<_>>();
+        let games_provider =
+            StubProvider::with_games(titles.iter().rev().map(|title| on_sale(title)).collect())
+                .failing_for("Game 3")
+                .failing_for("Game 7")
+                .with_concurrency(3);
+        let watched_games = titles.iter().map(WatchedGame::new).collect::<Vec<_>>();
+
+        // when
+        let result = acceptable_games(&watched_games, &games_provider);
+
+        // then
+        let found = result.games().iter().map(Game::title).collect::<Vec<_>>();
+        let expected = titles
+            .iter()
+            .filter(|title| *title != "Game 3" && *title != "Game 7")
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(found, expected);
+        assert_eq!(
+            result.failed_games(),
+            vec![WatchedGame::new("Game 3"), WatchedGame::new("Game 7")]
+        );
+        assert!(games_provider.max_in_flight() > 1);
+        assert!(games_provider.max_in_flight() <= 3);
+    }
+
+    #[test]
+    fn test_batches_are_split_by_region_and_size() {
+        testutils::setup_logger();
+        // given
+        let provider = StubProvider::with_games(vec![]).with_batch_size(2);
+        let watched_games = vec![
+            WatchedGame::new("Game 1"),
+            WatchedGame::new("Game 2").with_region(Region::De),
+            WatchedGame::new("Game 3"),
+            WatchedGame::new("Game 4"),
+        ];
+
+        // when
+        let batches = batches(&watched_games, &provider);
+
+        // then
+        let batches = batches
+            .into_iter()
+            .map(|batch| (batch.region, batch.indices))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            batches,
+            vec![
+                (Region::En, vec![0, 2]),
+                (Region::De, vec![1]),
+                (Region::En, vec![3]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_games_with_pattern_are_batched_alone() {
+        testutils::setup_logger();
+        // given
+        let provider = StubProvider::with_games(vec![]).with_batch_size(10);
+        let watched_games = vec![
+            WatchedGame::new("Game 1"),
+            WatchedGame::with_pattern("LEGO*", PatternSyntax::Glob),
+            WatchedGame::new("Game 2"),
+        ];
+
+        // when
+        let batches = batches(&watched_games, &provider);
+
+        // then
+        let batches = batches
+            .into_iter()
+            .map(|batch| batch.indices)
+            .collect::<Vec<_>>();
+        assert_eq!(batches, vec![vec![0, 2], vec![1]]);
+    }
+
+    #[test]
+    fn test_merge_retry_keeps_order_of_watched_games() {
+        testutils::setup_logger();
+        // given
+        let on_sale = |title: &str| Game {
+            title: title.into(),
+            price_has_discount_b: Some(true),
+            ..Game::default()
+        };
+        let games = vec![on_sale("Game 1"), on_sale("Game 2"), on_sale("Game 3")];
+        let watched_games = ["Game 1", "Game 2", "Game 3"]
+            .map(WatchedGame::new)
+            .to_vec();
+        let first = acceptable_games(
+            &watched_games,
+            &StubProvider::with_games(games.clone())
+                .failing_for("Game 1")
+                .failing_for("Game 3"),
+        );
+        let retry = acceptable_games(
+            &first.failed_games(),
+            &StubProvider::with_games(games.clone()).failing_for("Game 3"),
+        );
+
+        // when
+        let merged = first.merge_retry(retry);
+
+        // then
+        assert_eq!(merged.games(), vec![on_sale("Game 1"), on_sale("Game 2")]);
+        assert_eq!(merged.failed_games(), vec![WatchedGame::new("Game 3")]);
+
+        // when
+        let retry = acceptable_games(
+            &merged.failed_games(),
+            &StubProvider::with_games(games.clone()),
+        );
+        let merged = merged.merge_retry(retry);
+
+        // then
+        assert_eq!(merged.games(), games);
+    }
+
+    #[test]
+    fn test_merge_retry_replaces_failures() {
+        testutils::setup_logger();
+        // given
+        let watched_games = vec![WatchedGame::new("Game 1"), WatchedGame::new("Game 2")];
+        let first = acceptable_games(
+            &watched_games,
+            &StubProvider::with_games(vec![Game::default()]).failing_for("Game 2"),
+        );
+        let on_sale = Game {
+            title: "Game 2".into(),
+            price_has_discount_b: Some(true),
+            ..Game::default()
+        };
+        let retry = acceptable_games(
+            &first.failed_games(),
+            &StubProvider::with_games(vec![on_sale.clone()]),
+        );
+
+        // when
+        let merged = first.merge_retry(retry);
+
+        // then
+        assert_eq!(merged.games(), vec![on_sale]);
+        assert!(!merged.has_failures());
+        assert_eq!(merged.checked(), 2);
+    }
+
+    #[test]
+    fn test_acceptable_games_with_provider_without_sale_flag() {
+        testutils::setup_logger();
+        // given
+        let games_provider = StubProvider::with_games(vec![
+            Game {
+                title: "Game 1".into(),
+                price_discounted_f: Some(5.0),
+                price_regular_f: Some(7.0),
+                ..Game::default()
+            },
+            Game {
+                title: "Game 1".into(),
+                price_discounted_f: Some(7.0),
+                price_regular_f: Some(7.0),
+                ..Game::default()
+            },
+        ])
+        .without_sale_flag();
+
+        let watched_games = vec![WatchedGame::new("Game 1")];
+
+        // when
+        let filtered_games = acceptable_games(&watched_games, &games_provider).into_games();
+
+        // then
+        assert_eq!(
+            filtered_games,
+            vec![Game {
+                title: "Game 1".into(),
+                price_discounted_f: Some(5.0),
+                price_regular_f: Some(7.0),
+                ..Game::default()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_acceptable_games_matched_by_nsuid() {
+        testutils::setup_logger();
+        // given
+        let game = |title: &str, nsuid: &str| Game {
+            title: title.into(),
+            price_has_discount_b: Some(true),
+            nsuid_txt: vec![nsuid.into()],
+            ..Game::default()
+        };
+        let games_provider = StubProvider::with_games(vec![
+            game("Game 1", "70010000000001"),
+            game("Game 1", "70070000000002"),
+            game("Game 1 - Renamed", "70010000000003"),
+        ]);
+        let watched_games = vec![
+            WatchedGame::new("Game 1").with_nsuid("70010000000001"),
+            WatchedGame::new("Game 1").with_nsuid("70010000000003"),
+        ];
+
+        // when
+        let filtered_games = acceptable_games(&watched_games, &games_provider).into_games();
+
+        // then
+        assert_eq!(
+            filtered_games,
+            vec![
+                game("Game 1", "70010000000001"),
+                game("Game 1 - Renamed", "70010000000003")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_acceptable_games_matched_by_product_code() {
+        testutils::setup_logger();
+        // given
+        let game = |title: &str, product_code: &str| Game {
+            title: title.into(),
+            price_has_discount_b: Some(true),
+            product_code_txt: vec![product_code.into()],
+            ..Game::default()
+        };
+        let games_provider = StubProvider::with_games(vec![
+            game("Game 1", "HACPAAAAA"),
+            game("Game 1", "HACPBBBBB"),
+        ]);
+        let watched_games = vec![WatchedGame::new("Game 1").with_product_code("hacpbbbbb")];
+
+        // when
+        let filtered_games = acceptable_games(&watched_games, &games_provider).into_games();
+
+        // then
+        assert_eq!(filtered_games, vec![game("Game 1", "HACPBBBBB")]);
+    }
+
+    fn discounted(title: &str, regular: f64, discounted: f64) -> Game {
+        Game::with_prices(title, Some(regular), Some(discounted))
+    }
+
+    #[test]
+    fn test_acceptable_games_with_min_discount_percent() {
+        testutils::setup_logger();
+        // given
+        let games_provider = StubProvider::with_games(vec![
+            discounted("Game 1", 20.0, 10.0),
+            discounted("Game 2", 20.0, 15.0),
+            Game::with_prices("Game 3", Some(20.0), None),
+        ]);
+        let watched_games = ["Game 1", "Game 2", "Game 3"]
+            .map(|title| WatchedGame::new(title).with_min_discount_percent(50.0))
+            .to_vec();
+
+        // when
+        let filtered_games = acceptable_games(&watched_games, &games_provider).into_games();
+
+        // then
+        assert_eq!(filtered_games, vec![discounted("Game 1", 20.0, 10.0)]);
+    }
+
+    #[test]
+    fn test_acceptable_games_with_all_criteria() {
+        testutils::setup_logger();
+        // given
+        let games_provider = StubProvider::with_games(vec![
+            discounted("Game 1", 20.0, 10.0),
+            discounted("Game 2", 40.0, 20.0),
+            discounted("Game 3", 12.0, 10.0),
+        ]);
+        let watched_games = ["Game 1", "Game 2", "Game 3"]
+            .map(|title| {
+                WatchedGame::new(title)
+                    .with_acceptable_price(10.0)
+                    .with_min_discount_percent(50.0)
+            })
+            .to_vec();
+
+        // when
+        let filtered_games = acceptable_games(&watched_games, &games_provider).into_games();
+
+        // then
+        assert_eq!(filtered_games, vec![discounted("Game 1", 20.0, 10.0)]);
+    }
+
+    #[test]
+    fn test_acceptable_games_with_any_criteria() {
+        testutils::setup_logger();
+        // given
+        let games_provider = StubProvider::with_games(vec![
+            discounted("Game 1", 20.0, 10.0),
+            discounted("Game 2", 40.0, 20.0),
+            discounted("Game 3", 12.0, 10.0),
+            discounted("Game 4", 40.0, 30.0),
+        ]);
+        let watched_games = ["Game 1", "Game 2", "Game 3", "Game 4"]
+            .map(|title| {
+                WatchedGame::new(title)
+                    .with_acceptable_price(10.0)
+                    .with_min_discount_percent(50.0)
+                    .with_criteria(Criteria::Any)
+            })
+            .to_vec();
+
+        // when
+        let filtered_games = acceptable_games(&watched_games, &games_provider).into_games();
+
+        // then
+        assert_eq!(
+            filtered_games,
+            vec![
+                discounted("Game 1", 20.0, 10.0),
+                discounted("Game 2", 40.0, 20.0),
+                discounted("Game 3", 12.0, 10.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_acceptable_games_with_rule() {
+        testutils::setup_logger();
+        // given
+        let games_provider = StubProvider::with_games(vec![
+            discounted("Game 1", 20.0, 10.0),
+            discounted("Game 2", 20.0, 16.0).with_lowest_recorded_price(Some(18.0)),
+            discounted("Game 3", 20.0, 16.0).with_lowest_recorded_price(Some(15.0)),
+        ]);
+        let watched_games = ["Game 1", "Game 2", "Game 3"]
+            .map(|title| {
+                WatchedGame::new(title)
+                    .with_rule("discount >= 40% and price <= 15 or is_all_time_low")
+            })
+            .to_vec();
+
+        // when
+        let filtered_games = acceptable_games(&watched_games, &games_provider).into_games();
+
+        // then
+        assert_eq!(
+            filtered_games,
+            vec![
+                discounted("Game 1", 20.0, 10.0),
+                discounted("Game 2", 20.0, 16.0).with_lowest_recorded_price(Some(18.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_acceptable_games_near_all_time_low() {
+        testutils::setup_logger();
+        // given
+        let games_provider = StubProvider::with_games(vec![
+            discounted("Game 1", 20.0, 10.0),
+            discounted("Game 2", 20.0, 10.0).with_lowest_recorded_price(Some(9.5)),
+            discounted("Game 3", 20.0, 10.0).with_lowest_recorded_price(Some(8.0)),
+        ]);
+        let watched_games = ["Game 1", "Game 2", "Game 3"]
+            .map(|title| {
+                WatchedGame::new(title)
+                    .with_acceptable_price(15.0)
+                    .with_max_percent_above_low(10.0)
+            })
+            .to_vec();
+
+        // when
+        let filtered_games = acceptable_games(&watched_games, &games_provider).into_games();
+
+        // then
+        assert_eq!(
+            filtered_games,
+            vec![discounted("Game 2", 20.0, 10.0).with_lowest_recorded_price(Some(9.5))]
+        );
+    }
+
+    #[test]
+    fn test_acceptable_games_with_sale_ends() {
+        testutils::setup_logger();
+        // given
+        let end = Utc.with_ymd_and_hms(2022, 12, 15, 12, 0, 0).unwrap();
+        let game = |title: &str, nsuid: &str| {
+            Game::with_prices(title, Some(20.0), Some(10.0))
+                .with_nsuid(nsuid)
+                .in_region(Region::En)
+        };
+        let games_provider = StubProvider::with_games(vec![
+            game("Game 1", "70010000000001"),
+            game("Game 2", "70010000000002"),
+        ])
+        .without_sale_flag()
+        .with_sale_end("70010000000001", end);
+        let watched_games = ["Game 1", "Game 2"]
+            .map(|title| WatchedGame::new(title).with_acceptable_price(15.0))
+            .to_vec();
+
+        // when
+        let filtered_games = acceptable_games(&watched_games, &games_provider).into_games();
+
+        // then
+        assert_eq!(
+            filtered_games,
+            vec![
+                game("Game 1", "70010000000001").with_sale_end(Some(end)),
+                game("Game 2", "70010000000002"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_acceptable_games_with_price_drop() {
+        testutils::setup_logger();
+        // given
+        let regular = |title, price| Game::with_prices(title, Some(price), None);
+        let games_provider = StubProvider::with_games(vec![
+            regular("Game 1", 15.0),
+            regular("Game 2", 15.0).with_last_recorded_price(Some(20.0)),
+            regular("Game 3", 15.0).with_last_recorded_price(Some(15.0)),
+        ]);
+        let watched_games = ["Game 1", "Game 2", "Game 3"]
+            .map(|title| WatchedGame::new(title).with_notify_on_price_drop())
+            .to_vec();
+
+        // when
+        let filtered_games = acceptable_games(&watched_games, &games_provider).into_games();
+
+        // then
+        assert_eq!(
+            filtered_games,
+            vec![regular("Game 2", 15.0).with_last_recorded_price(Some(20.0))]
+        );
+    }
+
+    #[test]
+    fn test_is_new_all_time_low() {
+        testutils::setup_logger();
+        // given
+        let games = [
+            discounted("Game 1", 20.0, 10.0),
+            discounted("Game 1", 20.0, 10.0).with_lowest_recorded_price(Some(10.0)),
+            discounted("Game 1", 20.0, 10.0).with_lowest_recorded_price(Some(12.0)),
+        ];
+
+        // when
+        let new_lows = games.map(|game| game.is_new_all_time_low());
+
+        // then
+        assert_eq!(new_lows, [false, false, true]);
+    }
+
+    #[test]
+    fn test_is_all_time_low() {
+        testutils::setup_logger();
+        // given
+        let games = [
+            discounted("Game 1", 20.0, 10.0),
+            discounted("Game 1", 20.0, 10.0).with_lowest_recorded_price(Some(10.0)),
+            discounted("Game 1", 20.0, 10.0).with_lowest_recorded_price(Some(8.0)),
+        ];
+
+        // when
+        let all_time_lows = games.map(|game| game.is_all_time_low());
+
+        // then
+        assert_eq!(all_time_lows, [false, true, false]);
+    }
+
+    #[test]
+    fn test_discount_percent() {
+        testutils::setup_logger();
+        // given
+        let games = [
+            discounted("Game 1", 20.0, 5.0),
+            discounted("Game 2", 20.0, 20.0),
+            Game::with_prices("Game 3", Some(20.0), None),
+            Game::with_prices("Game 4", None, Some(5.0)),
+        ];
+
+        // when
+        let discounts = games.map(|game| game.discount_percent());
+
+        // then
+        assert_eq!(discounts, [Some(75.0), None, None, None]);
     }
 
     #[test]
@@ -511,6 +1522,15 @@ mod test {
             price_discounted_f: None,
             price_regular_f: None,
             price_has_discount_b: None,
+            nsuid_txt: Vec::new(),
+            product_code_txt: Vec::new(),
+            region: None,
+            lowest_recorded_price: None,
+            last_recorded_price: None,
+            sale_end: None,
+            url: None,
+            image_url_sq_s: None,
+            price_discount_percentage_f: None,
         };
 
         // when
@@ -1,5 +1,274 @@
+use crate::data_providers::{Capabilities, GameProvider};
+use crate::entities::{Region, WatchedGame};
+use crate::switch::Game;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tempfile::TempDir;
+
 pub(crate) fn setup_logger() {
     let _logger_res = flexi_logger::Logger::try_with_str("debug")
         .expect("failed to initialize logger")
         .start();
 }
+
+/// Fixed time of checks in tests: 19:00 UTC of given day of December 2022.
+pub(crate) fn day(day: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2022, 12, day, 19, 0, 0).unwrap()
+}
+
+/// State loaded from a file in a new temporary directory, which is removed with the returned
+/// `TempDir`.
+pub(crate) fn load_in_temp_dir<T, F>(file_name: &str, load: F) -> (TempDir, T)
+where
+    F: FnOnce(PathBuf) -> Result<T>,
+{
+    let dir = TempDir::new().unwrap();
+    let state = load(dir.path().join(file_name)).unwrap();
+    (dir, state)
+}
+
+/// Provider returning predefined result instead of calling a store.
+pub(crate) struct StubProvider {
+    result: Result<Vec<Game>, String>,
+    capabilities: Capabilities,
+    failing_titles: Vec<String>,
+    concurrency: usize,
+    in_flight: AtomicUsize,
+    max_in_flight: AtomicUsize,
+    sale_ends: HashMap<String, DateTime<Utc>>,
+}
+
+impl StubProvider {
+    pub(crate) fn with_games(games: Vec<Game>) -> Self {
+        Self {
+            result: Ok(games),
+            capabilities: Capabilities {
+                sale_flag: true,
+                batch_size: 1,
+                store_id_search: true,
+            },
+            failing_titles: Vec::new(),
+            concurrency: 1,
+            in_flight: AtomicUsize::new(0),
+            max_in_flight: AtomicUsize::new(0),
+            sale_ends: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn failing<S: Into<String>>(error: S) -> Self {
+        Self {
+            result: Err(error.into()),
+            capabilities: Capabilities {
+                sale_flag: true,
+                batch_size: 1,
+                store_id_search: true,
+            },
+            failing_titles: Vec::new(),
+            concurrency: 1,
+            in_flight: AtomicUsize::new(0),
+            max_in_flight: AtomicUsize::new(0),
+            sale_ends: HashMap::new(),
+        }
+    }
+
+    /// Fails only for watched games with given title.
+    pub(crate) fn failing_for<S: Into<String>>(mut self, title: S) -> Self {
+        self.failing_titles.push(title.into());
+        self
+    }
+
+    pub(crate) fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.capabilities.batch_size = batch_size;
+        self
+    }
+
+    /// Fetches slow down, so concurrent fetches overlap.
+    pub(crate) fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Max number of fetches which were running at the same time.
+    pub(crate) fn max_in_flight(&self) -> usize {
+        self.max_in_flight.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn without_sale_flag(mut self) -> Self {
+        self.capabilities.sale_flag = false;
+        self
+    }
+
+    /// Sale of the game with given NSUID ends at `end` in every region.
+    pub(crate) fn with_sale_end<S: Into<String>>(mut self, nsuid: S, end: DateTime<Utc>) -> Self {
+        self.sale_ends.insert(nsuid.into(), end);
+        self
+    }
+}
+
+impl GameProvider for StubProvider {
+    fn name(&self) -> &'static str {
+        "stub"
+    }
+
+    fn regions(&self) -> &'static [Region] {
+        &[Region::En, Region::De]
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    fn fetch(&self, watched_game: &WatchedGame, _region: Region) -> Result<Vec<Game>> {
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+        if self.concurrency > 1 {
+            thread::sleep(Duration::from_millis(10));
+        }
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        if self.failing_titles.contains(&watched_game.title()) {
+            return Err(anyhow!("failed to fetch {}", watched_game.title()));
+        }
+        match &self.result {
+            Ok(games) => Ok(games.clone()),
+            Err(e) => Err(anyhow!(e.clone())),
+        }
+    }
+
+    fn sale_ends(
+        &self,
+        nsuids: &[String],
+        _region: Region,
+    ) -> Result<HashMap<String, DateTime<Utc>>> {
+        Ok(nsuids
+            .iter()
+            .filter_map(|nsuid| Some((nsuid.clone(), *self.sale_ends.get(nsuid)?)))
+            .collect())
+    }
+}
+
+/// Response returned by [`MockServer`].
+#[derive(Debug, Clone)]
+pub(crate) struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl MockResponse {
+    pub(crate) fn ok<S: Into<String>>(body: S) -> Self {
+        Self {
+            status: 200,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    pub(crate) fn status(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: String::new(),
+        }
+    }
+
+    pub(crate) fn with_header<S: Into<String>>(mut self, name: S, value: S) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    fn to_http(&self) -> String {
+        let mut response = format!(
+            "HTTP/1.1 {} Mock\r\nContent-Length: {}\r\nConnection: close\r\n",
+            self.status,
+            self.body.len()
+        );
+        for (name, value) in &self.headers {
+            let _ignored = write!(response, "{name}: {value}\r\n");
+        }
+        response.push_str("\r\n");
+        response.push_str(&self.body);
+        response
+    }
+}
+
+/// Local HTTP server answering with predefined responses, one per request. When responses run
+/// out, the last one is repeated.
+pub(crate) struct MockServer {
+    addr: SocketAddr,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+    pub(crate) fn start(responses: Vec<MockResponse>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind mock server");
+        let addr = listener
+            .local_addr()
+            .expect("failed to get mock server address");
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&requests);
+        thread::spawn(move || {
+            for (idx, stream) in listener.incoming().enumerate() {
+                let Ok(mut stream) = stream else { break };
+                recorded
+                    .lock()
+                    .expect("poisoned lock")
+                    .push(read_request_target(&stream));
+                let response = responses.get(idx).or(responses.last());
+                if let Some(response) = response {
+                    let _ignored = stream.write_all(response.to_http().as_bytes());
+                }
+            }
+        });
+        Self { addr, requests }
+    }
+
+    /// Url on which nothing listens.
+    pub(crate) fn unused_url() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind");
+        let addr = listener.local_addr().expect("failed to get address");
+        format!("http://{addr}/")
+    }
+
+    pub(crate) fn url(&self, path: &str) -> String {
+        format!("http://{}{path}", self.addr)
+    }
+
+    /// Paths with query strings of received requests.
+    pub(crate) fn requests(&self) -> Vec<String> {
+        self.requests.lock().expect("poisoned lock").clone()
+    }
+
+    pub(crate) fn requests_count(&self) -> usize {
+        self.requests().len()
+    }
+}
+
+fn read_request_target(stream: &TcpStream) -> String {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    let _ignored = reader.read_line(&mut request_line);
+    // skip headers, mock server accepts only requests without body
+    let mut line = String::new();
+    while reader.read_line(&mut line).is_ok_and(|read| read > 2) {
+        line.clear();
+    }
+    request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or_default()
+        .to_string()
+}